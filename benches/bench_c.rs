@@ -465,9 +465,9 @@ fn bench_parse_c(b: &mut test::Bencher) {
         // tok.map(|t| (t.usize() as u32, start, end))
         tok.map(|t| t.usize() as u32)
     }).collect();
+    let cfg = InternalGrammar::from_grammar_with_names(&external, SYM_NAMES);
     let mut first = true;
     b.iter(|| {
-        let cfg = InternalGrammar::from_grammar(&external);
         let bocage = Bocage::new(&cfg);
         let mut rec: Recognizer<Bocage<&'_ InternalGrammar>> = Recognizer::new_with_limit(&cfg, 2_00_000);
         rec.forest = bocage;
@@ -47,6 +47,11 @@ impl<'g, F> Recognizer<'g, F>
     /// Removes the greatest item from the binary heap and returns it, or `None` if it
     /// is empty.
     pub fn heap_pop(&mut self) -> Option<CompletedItem<F::NodeRef>> {
+        if !self.complete.is_empty() {
+            if let Some(ref mut stats) = self.stats {
+                stats.heap_pops += 1;
+            }
+        }
         self.complete.pop().and_then(move |mut right_item| {
             if !self.complete.is_empty() {
                 swap(&mut right_item, &mut self.complete[0]);
@@ -73,6 +78,9 @@ impl<'g, F> Recognizer<'g, F>
             idx: old_medial_len as u32,
             node: item.right_node,
         });
+        if let Some(ref mut stats) = self.stats {
+            stats.heap_pushes += 1;
+        }
         self.sift_up(0, old_indices_len);
     }
 
@@ -80,6 +88,9 @@ impl<'g, F> Recognizer<'g, F>
     pub fn heap_push_linked(&mut self, item: CompletedItemLinked<F::NodeRef>) {
         let old_indices_len = self.complete.len();
         self.complete.push(item);
+        if let Some(ref mut stats) = self.stats {
+            stats.heap_pushes += 1;
+        }
         self.sift_up(0, old_indices_len);
     }
 
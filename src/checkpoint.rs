@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use bit_matrix::Block;
+use cfg::Symbol;
+
+use forest::Forest;
+use item::{CompletedItemLinked, PendingToken};
+use recognizer::Recognizer;
+use stats::ParseStats;
+
+/// A saved state of the recognizer, which can be restored with `rollback`.
+///
+/// Earlier Earley sets are kept while a checkpoint is outstanding, so every checkpoint
+/// should be either rolled back or committed.
+#[derive(Clone, Debug)]
+pub struct Checkpoint<N> {
+    earleme: usize,
+    indices_len: usize,
+    medial_len: usize,
+    current_medial_start: usize,
+    // The current row of predicted symbols, which may still change.
+    predicted_row: Vec<Block>,
+    complete: Vec<CompletedItemLinked<N>>,
+    pending: BTreeMap<usize, Vec<PendingToken<N>>>,
+    lookahead_hint: Vec<Option<Symbol>>,
+    leo_len: usize,
+    graph_len: usize,
+    stats: Option<ParseStats>,
+    // The number of checkpoints that were outstanding before this one.
+    depth: usize,
+}
+
+impl<N> Checkpoint<N> {
+    /// Returns the location at which the checkpoint was taken.
+    pub fn earleme(&self) -> usize {
+        self.earleme
+    }
+}
+
+impl<'g, F> Recognizer<'g, F>
+where
+    F: Forest,
+{
+    /// Saves the current state of the recognizer. Until the checkpoint is rolled back or
+    /// committed, earlier Earley sets are not removed.
+    pub fn checkpoint(&mut self) -> Checkpoint<F::NodeRef> {
+        let checkpoint = Checkpoint {
+            earleme: self.earleme,
+            indices_len: self.indices.len(),
+            medial_len: self.medial.len(),
+            current_medial_start: self.current_medial_start,
+            predicted_row: self.predicted[self.earleme].to_vec(),
+            complete: self.complete.clone(),
+            pending: self.pending.clone(),
+            lookahead_hint: self.lookahead_hint.clone(),
+            leo_len: self.leo.len(),
+            graph_len: self.forest.graph_len(),
+            stats: self.stats.clone(),
+            depth: self.checkpoint_depth,
+        };
+        self.checkpoint_depth += 1;
+        checkpoint
+    }
+
+    /// Restores the state saved by the given checkpoint. Forest nodes created after
+    /// the checkpoint are removed. Checkpoints taken after the given one become invalid.
+    ///
+    /// Statistics are restored as well, unless they were enabled or disabled after the
+    /// checkpoint.
+    pub fn rollback(&mut self, checkpoint: Checkpoint<F::NodeRef>) {
+        self.earleme = checkpoint.earleme;
+        self.indices.truncate(checkpoint.indices_len);
+        self.medial.truncate(checkpoint.medial_len);
+        self.current_medial_start = checkpoint.current_medial_start;
+        self.predicted.truncate(checkpoint.earleme + 1);
+        self.predicted[checkpoint.earleme].copy_from_slice(&checkpoint.predicted_row[..]);
+        self.complete = checkpoint.complete;
+        self.pending = checkpoint.pending;
+        self.lookahead_hint = checkpoint.lookahead_hint;
+        self.leo.truncate(checkpoint.leo_len);
+        self.forest.truncate_graph(checkpoint.graph_len);
+        if let (Some(stats), Some(saved)) = (&mut self.stats, checkpoint.stats) {
+            *stats = saved;
+        }
+        self.checkpoint_depth = checkpoint.depth;
+    }
+
+    /// Keeps the progress made since the given checkpoint and releases it, along with
+    /// checkpoints taken after it.
+    pub fn commit(&mut self, checkpoint: Checkpoint<F::NodeRef>) {
+        self.checkpoint_depth = checkpoint.depth;
+    }
+}
@@ -1,6 +1,10 @@
 use std::fmt;
+use std::io::{self, Write};
+
+use cfg::Symbol;
 
 use forest::Forest;
+use item::Dot;
 use recognizer::Recognizer;
 
 impl<'g, F: Forest> fmt::Debug for Recognizer<'g, F> {
@@ -9,14 +13,160 @@ impl<'g, F: Forest> fmt::Debug for Recognizer<'g, F> {
             "Recognizer {{ grammar: {:?}, \
             predicted: {:?}, medial: {:?}, \
             complete: {:?}, indices: {:?}, \
-            current_medial_start: {:?}, earleme: {:?} }}",
+            current_medial_start: {:?}, earleme: {:?}, \
+            pending: {:?}, leo: {:?} }}",
 			self.grammar,
 			&self.predicted,
 			&self.medial,
 			&self.complete,
 			&self.indices,
 			&self.current_medial_start,
-			&self.earleme
+			&self.earleme,
+			&self.pending,
+			&self.leo
 		)
 	}
 }
+
+impl<'g, F: Forest> Recognizer<'g, F> {
+    /// Writes the retained Earley sets in a readable form. Each set lists its medial items
+    /// as external dotted rules with their origins, then the rules and terminals it predicts.
+    pub fn dump_chart<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let grammar = self.grammar;
+        for set in 0..self.indices.len() - 1 {
+            writeln!(out, "set {}:", set)?;
+            for item in &self.medial[self.indices[set]..self.indices[set + 1]] {
+                write!(out, "  ")?;
+                self.dump_item(out, item.dot)?;
+                writeln!(out, ", origin {}", item.origin)?;
+            }
+            for leo in self.leo.iter().filter(|leo| leo.set as usize == set) {
+                write!(out, "  leo ")?;
+                self.dump_symbol(out, leo.symbol)?;
+                write!(out, ": ")?;
+                let top = &self.medial[leo.top as usize];
+                self.dump_item(out, top.dot)?;
+                writeln!(out, ", origin {}", top.origin)?;
+            }
+            let predicted = &self.predicted[set];
+            let mut rules = vec![];
+            for dot in 0..grammar.num_rules() as Dot {
+                if let Some(rule) = grammar.external_origin(dot) {
+                    let lhs = grammar.get_lhs(dot);
+                    if predicted.get(lhs.usize()) && !rules.contains(&rule) {
+                        rules.push(rule);
+                        write!(out, "  ")?;
+                        self.dump_rule(out, rule, 0)?;
+                        self.dump_dotted_rule(out, rule, 0, dot, None)?;
+                        writeln!(out)?;
+                    }
+                }
+            }
+            let mut expected = (0..grammar.num_syms())
+                .map(Symbol::from)
+                .filter(|&symbol| predicted.get(symbol.usize()) && grammar.is_terminal(symbol))
+                .peekable();
+            if expected.peek().is_some() {
+                write!(out, "  expected:")?;
+                for symbol in expected {
+                    write!(out, " ")?;
+                    self.dump_symbol(out, symbol)?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Writes a medial item, which has one symbol after the dot.
+    fn dump_item<W: Write>(&self, out: &mut W, dot: Dot) -> io::Result<()> {
+        let grammar = self.grammar;
+        let postdot = grammar.get_rhs1(dot).unwrap();
+        match grammar.trace()[1][dot as usize] {
+            Some((rule, position)) => {
+                self.dump_rule(out, rule, position)?;
+                self.dump_dotted_rule(out, rule, position, dot, Some(postdot))
+            }
+            None => {
+                if dot == grammar.dot_before_eof() {
+                    write!(out, "<start> -> ")?;
+                } else {
+                    write!(out, "internal: ")?;
+                    self.dump_symbol(out, grammar.get_lhs(dot))?;
+                    write!(out, " -> ")?;
+                }
+                self.dump_symbol(out, grammar.get_rhs0(dot).unwrap())?;
+                write!(out, " · ")?;
+                self.dump_symbol(out, postdot)
+            }
+        }
+    }
+
+    // Writes an external rule with the dot before the symbol at `position`. Rules of grammars
+    // that were binarized elsewhere are written around the item's dot, with their LHS found
+    // through `dot` and other symbols elided.
+    fn dump_dotted_rule<W: Write>(
+        &self,
+        out: &mut W,
+        rule: u32,
+        position: u32,
+        dot: Dot,
+        postdot: Option<Symbol>,
+    ) -> io::Result<()> {
+        let grammar = self.grammar;
+        let position = position as usize;
+        match grammar.external_rule(rule) {
+            Some((lhs, rhs)) if position <= rhs.len() => {
+                write!(out, "{} ->", grammar.display_symbol(lhs))?;
+                for (i, &symbol) in rhs.iter().enumerate() {
+                    if i == position {
+                        write!(out, " ·")?;
+                    }
+                    write!(out, " {}", grammar.display_symbol(symbol))?;
+                }
+                if position == rhs.len() {
+                    write!(out, " ·")?;
+                }
+                Ok(())
+            }
+            _ => {
+                let lhs = (0..grammar.num_rules() as Dot)
+                    .find(|&dot| grammar.external_origin(dot) == Some(rule))
+                    .map(|dot| grammar.get_lhs(dot))
+                    .unwrap_or_else(|| grammar.get_lhs(dot));
+                self.dump_symbol(out, lhs)?;
+                match postdot {
+                    Some(postdot) => {
+                        if position == 1 {
+                            write!(out, " -> ")?;
+                            self.dump_symbol(out, grammar.get_rhs0(dot).unwrap())?;
+                            write!(out, " · ")?;
+                        } else {
+                            write!(out, " -> … · ")?;
+                        }
+                        self.dump_symbol(out, postdot)
+                    }
+                    None => write!(out, " -> · …"),
+                }
+            }
+        }
+    }
+
+    // Writes an internal symbol by the name of its external symbol.
+    fn dump_symbol<W: Write>(&self, out: &mut W, symbol: Symbol) -> io::Result<()> {
+        let grammar = self.grammar;
+        if symbol == grammar.eof() {
+            write!(out, "<eof>")
+        } else {
+            write!(out, "{}", grammar.display_symbol(grammar.to_external(symbol)))
+        }
+    }
+
+    // Writes the name or number of an external rule, along with the dot position.
+    fn dump_rule<W: Write>(&self, out: &mut W, rule: u32, position: u32) -> io::Result<()> {
+        match self.grammar.rule_name(rule) {
+            Some(name) => write!(out, "{} at {}: ", name, position),
+            None => write!(out, "rule {} at {}: ", rule, position),
+        }
+    }
+}
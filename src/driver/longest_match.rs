@@ -0,0 +1,90 @@
+use cfg::Symbol;
+
+use forest::Forest;
+use recognizer::Recognizer;
+
+/// A driver that parses the longest prefix of the input that derives the start symbol.
+///
+/// Locations count the earlemes advanced by the driver, so they are not affected by
+/// the removal of earlier Earley sets.
+pub struct LongestMatch<'g, F>
+where
+    F: Forest,
+{
+    /// The recognizer.
+    pub recognizer: Recognizer<'g, F>,
+    // The current input location.
+    location: usize,
+    // Locations where the parse finished, with their finished nodes.
+    finished: Vec<(usize, F::NodeRef)>,
+}
+
+impl<'g, F> LongestMatch<'g, F>
+where
+    F: Forest,
+{
+    /// Creates a driver for a recognizer at its initial location.
+    pub fn new(recognizer: Recognizer<'g, F>) -> Self {
+        let mut driver = LongestMatch {
+            recognizer,
+            location: 0,
+            finished: vec![],
+        };
+        driver.record_finished();
+        driver
+    }
+
+    /// Reads a token.
+    pub fn scan(&mut self, symbol: Symbol, value: F::LeafValue) {
+        self.recognizer.scan(symbol, value);
+    }
+
+    /// Advances the parse and records the location if the parse finishes there.
+    /// Returns `false` when the recognizer is exhausted.
+    pub fn end_earleme(&mut self) -> bool {
+        if self.recognizer.end_earleme() {
+            self.location += 1;
+            self.record_finished();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn record_finished(&mut self) {
+        if let Some(node) = self.recognizer.finished_node() {
+            self.finished.push((self.location, node));
+        }
+    }
+
+    /// Reads tokens until the recognizer is exhausted or the input ends. Returns
+    /// the longest match.
+    pub fn parse<I>(&mut self, tokens: I) -> Option<(usize, F::NodeRef)>
+    where
+        I: IntoIterator<Item = (Symbol, F::LeafValue)>,
+    {
+        for (symbol, value) in tokens {
+            self.scan(symbol, value);
+            if !self.end_earleme() {
+                break;
+            }
+        }
+        self.longest_match()
+    }
+
+    /// Returns the current input location.
+    pub fn location(&self) -> usize {
+        self.location
+    }
+
+    /// Returns every location where the parse finished, with its finished node,
+    /// in increasing order.
+    pub fn finished(&self) -> &[(usize, F::NodeRef)] {
+        &self.finished[..]
+    }
+
+    /// Returns the longest finished location with its finished node.
+    pub fn longest_match(&self) -> Option<(usize, F::NodeRef)> {
+        self.finished.last().cloned()
+    }
+}
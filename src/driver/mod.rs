@@ -0,0 +1,7 @@
+//! Drivers run the recognizer over a token stream in a particular parsing mode.
+
+pub mod longest_match;
+pub mod substring;
+
+pub use self::longest_match::LongestMatch;
+pub use self::substring::{Match, Substring};
@@ -0,0 +1,105 @@
+use cfg::Symbol;
+
+use forest::Forest;
+use recognizer::Recognizer;
+
+/// A driver that finds every span of the input that derives the start symbol. The start
+/// symbol is predicted anew at every location.
+pub struct Substring<'g, F>
+where
+    F: Forest,
+{
+    /// The recognizer.
+    pub recognizer: Recognizer<'g, F>,
+    // The current input location.
+    location: usize,
+    // Input locations of Earley sets.
+    set_locations: Vec<usize>,
+    // Matches found so far.
+    matches: Vec<Match<F::NodeRef>>,
+}
+
+/// A span `[start, end)` of the input that derives the start symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match<N> {
+    /// The input location where the match begins.
+    pub start: usize,
+    /// The input location where the match ends.
+    pub end: usize,
+    /// The forest node of the start symbol.
+    pub node: N,
+}
+
+impl<'g, F> Substring<'g, F>
+where
+    F: Forest,
+{
+    /// Creates a driver for a recognizer at its initial location.
+    pub fn new(recognizer: Recognizer<'g, F>) -> Self {
+        Substring {
+            recognizer,
+            location: 0,
+            set_locations: vec![0],
+            matches: vec![],
+        }
+    }
+
+    /// Reads a token.
+    pub fn scan(&mut self, symbol: Symbol, value: F::LeafValue) {
+        self.recognizer.scan(symbol, value);
+    }
+
+    /// Advances the parse and records matches that end at the new location. Unlike
+    /// the recognizer, the driver is never exhausted.
+    pub fn end_earleme(&mut self) {
+        if !self.recognizer.end_earleme() {
+            // Nothing to complete. Skip to the next location.
+            self.recognizer.advance_without_completion();
+        }
+        self.location += 1;
+        // Earlier sets may have been removed, along with their locations.
+        let earleme = self.recognizer.earleme();
+        self.set_locations.truncate(earleme);
+        self.set_locations.push(self.location);
+        self.record_matches();
+        let start_sym = self.recognizer.grammar.start_sym();
+        self.recognizer.predict(start_sym);
+    }
+
+    fn record_matches(&mut self) {
+        let recognizer = &self.recognizer;
+        let earleme = recognizer.earleme;
+        let set = &recognizer.medial[recognizer.indices[earleme]..recognizer.indices[earleme + 1]];
+        for item in set {
+            if item.dot == recognizer.grammar.dot_before_eof() {
+                self.matches.push(Match {
+                    start: self.set_locations[item.origin as usize],
+                    end: self.location,
+                    node: item.node,
+                });
+            }
+        }
+    }
+
+    /// Reads all tokens and returns every match.
+    pub fn find_all<I>(&mut self, tokens: I) -> &[Match<F::NodeRef>]
+    where
+        I: IntoIterator<Item = (Symbol, F::LeafValue)>,
+    {
+        for (symbol, value) in tokens {
+            self.scan(symbol, value);
+            self.end_earleme();
+        }
+        self.matches()
+    }
+
+    /// Returns the current input location.
+    pub fn location(&self) -> usize {
+        self.location
+    }
+
+    /// Returns every non-empty match found so far, ordered by the location where it ends.
+    pub fn matches(&self) -> &[Match<F::NodeRef>] {
+        &self.matches[..]
+    }
+}
@@ -0,0 +1,186 @@
+use std::error::Error;
+use std::fmt;
+
+use cfg::Symbol;
+
+use forest::Forest;
+use grammar::{DisplaySymbol, InternalGrammar};
+use recognizer::Recognizer;
+
+/// An error that describes why the recognizer can't accept more input at some location.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// The location where the parse failed.
+    pub earleme: usize,
+    /// The rejected token, or `None` when the recognizer is exhausted.
+    pub token: Option<Symbol>,
+    /// External terminals that can be scanned at the location.
+    pub expected: Vec<Symbol>,
+    /// Partially completed rules at the location, given as external dotted rules
+    /// along with their origins.
+    pub partial_rules: Vec<((u32, u32), usize)>,
+}
+
+impl<'g, F> Recognizer<'g, F>
+where
+    F: Forest,
+{
+    /// Creates an error that describes the current location, optionally with a rejected token.
+    pub fn parse_error(&self, token: Option<Symbol>) -> ParseError {
+        ParseError {
+            earleme: self.earleme(),
+            token,
+            expected: self.expected_external_terminals().collect(),
+            partial_rules: self.trace().collect(),
+        }
+    }
+}
+
+impl ParseError {
+    /// Returns a value that displays the error with symbol names taken from the grammar.
+    pub fn display<'a>(&'a self, grammar: &'a InternalGrammar) -> DisplayParseError<'a> {
+        DisplayParseError {
+            error: self,
+            grammar: Some(grammar),
+        }
+    }
+}
+
+/// Displays a parse error with symbol names.
+pub struct DisplayParseError<'a> {
+    error: &'a ParseError,
+    grammar: Option<&'a InternalGrammar>,
+}
+
+impl<'a> DisplayParseError<'a> {
+    fn symbol(&self, f: &mut fmt::Formatter, symbol: Symbol) -> fmt::Result {
+        match self.grammar {
+            Some(grammar) => write!(f, "{}", grammar.display_symbol(symbol)),
+            None => write!(f, "{}", symbol.usize()),
+        }
+    }
+}
+
+impl<'a> fmt::Display for DisplayParseError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let error = self.error;
+        match error.token {
+            Some(token) => {
+                write!(
+                    f,
+                    "parse error at earleme {}: unexpected token ",
+                    error.earleme
+                )?;
+                self.symbol(f, token)?;
+            }
+            None => write!(f, "parse error at earleme {}: exhausted", error.earleme)?,
+        }
+        if !error.expected.is_empty() {
+            write!(f, "; expected one of:")?;
+            for &symbol in &error.expected {
+                write!(f, " ")?;
+                self.symbol(f, symbol)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        DisplayParseError {
+            error: self,
+            grammar: None,
+        }
+        .fmt(f)
+    }
+}
+
+impl Error for ParseError {}
+
+/// An error that occurs when a recognizer is restored from a snapshot.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotError {
+    /// The snapshot was taken with a different grammar.
+    GrammarMismatch,
+    /// The snapshot is truncated or otherwise invalid.
+    Malformed,
+    /// The forest can't be saved in a snapshot.
+    UnsupportedForest,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::GrammarMismatch => {
+                write!(f, "snapshot was taken with a different grammar")
+            }
+            SnapshotError::Malformed => write!(f, "malformed snapshot"),
+            SnapshotError::UnsupportedForest => {
+                write!(f, "forest can't be saved in a snapshot")
+            }
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+/// An error that describes why a grammar can't be used for parsing. Symbols are external.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GrammarError {
+    /// The start symbol has no rules.
+    MissingStart,
+    /// Symbols that don't derive any string of terminals.
+    Unproductive(Vec<Symbol>),
+    /// Symbols that can't be reached from the start symbol.
+    Inaccessible(Vec<Symbol>),
+    /// Symbols that derive themselves through unit rules, possibly after nullable symbols
+    /// are omitted.
+    Cycle(Vec<Symbol>),
+    /// Symbols that have more than one empty rule, counting sequences that may be empty.
+    DuplicateEmptyRule(Vec<Symbol>),
+    /// The grammar has more rules or nulling symbols than dots and forest nodes can hold.
+    TooLarge,
+}
+
+impl GrammarError {
+    /// Returns a value that displays the error with symbol names from a list that is
+    /// indexed by external symbols.
+    pub fn display<'a>(&'a self, names: &'a [&'a str]) -> DisplayGrammarError<'a> {
+        DisplayGrammarError { error: self, names }
+    }
+}
+
+/// Displays a grammar error with symbol names.
+pub struct DisplayGrammarError<'a> {
+    error: &'a GrammarError,
+    names: &'a [&'a str],
+}
+
+impl<'a> fmt::Display for DisplayGrammarError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (message, symbols) = match *self.error {
+            GrammarError::MissingStart => return write!(f, "start symbol has no rules"),
+            GrammarError::Unproductive(ref symbols) => ("unproductive symbols", symbols),
+            GrammarError::Inaccessible(ref symbols) => ("inaccessible symbols", symbols),
+            GrammarError::Cycle(ref symbols) => ("symbols in a cycle", symbols),
+            GrammarError::DuplicateEmptyRule(ref symbols) => {
+                ("symbols with duplicate empty rules", symbols)
+            }
+            GrammarError::TooLarge => return write!(f, "grammar is too large"),
+        };
+        write!(f, "{}:", message)?;
+        for &symbol in symbols {
+            write!(f, " {}", DisplaySymbol::from_names(self.names, symbol))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.display(&[]).fmt(f)
+    }
+}
+
+impl Error for GrammarError {}
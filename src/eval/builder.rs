@@ -0,0 +1,239 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use cfg::Symbol;
+
+use eval::{Ambiguity, AmbiguityError, Evaluation, Evaluator};
+use forest::bocage::traverse as bocage;
+use forest::bocage::LeafPayload;
+use forest::compact_bocage::traverse as compact_bocage;
+use forest::node_handle::NodeHandle;
+use grammar::InternalGrammar;
+
+type RuleFn<'a, V> = Box<dyn FnMut(&[&V]) -> V + 'a>;
+type TerminalFn<'a, V, L> = Box<dyn FnMut(L) -> V + 'a>;
+type ErrorFn<'a, V> = Box<dyn FnMut(u32) -> V + 'a>;
+type NullingFn<'a, V> = Box<dyn FnMut() -> V + 'a>;
+
+/// Builds a typed tree with constructors registered for external rule ids and symbols.
+/// Tokens carry values of type `L`.
+pub struct Builder<'a, V, L = u32> {
+    rules: BTreeMap<u32, RuleFn<'a, V>>,
+    terminals: BTreeMap<Symbol, TerminalFn<'a, V, L>>,
+    errors: BTreeMap<Symbol, ErrorFn<'a, V>>,
+    nulling: BTreeMap<Symbol, NullingFn<'a, V>>,
+}
+
+/// An error that occurs when a tree can't be built.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BuildError {
+    /// No constructor is registered for the rule with the given external id.
+    MissingRule(u32),
+    /// No constructor is registered for the terminal.
+    MissingTerminal(Symbol),
+    /// No constructor is registered for error tokens of the symbol.
+    MissingError(Symbol),
+    /// No constructor is registered for the nulling symbol.
+    MissingNulling(Symbol),
+    /// The forest has more than one tree.
+    Ambiguous(AmbiguityError),
+    /// The traversal gave no value for the root, which wasn't marked alive.
+    NoTree,
+}
+
+impl<'a, V, L> Builder<'a, V, L> {
+    pub fn new() -> Self {
+        Builder {
+            rules: BTreeMap::new(),
+            terminals: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            nulling: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a constructor for the rule with the given external id. It receives
+    /// values of the rule's RHS symbols.
+    pub fn rule<F>(&mut self, rule: u32, constructor: F) -> &mut Self
+    where
+        F: FnMut(&[&V]) -> V + 'a,
+    {
+        self.rules.insert(rule, Box::new(constructor));
+        self
+    }
+
+    /// Registers a constructor for a terminal. It receives the token's value.
+    pub fn terminal<F>(&mut self, symbol: Symbol, constructor: F) -> &mut Self
+    where
+        F: FnMut(L) -> V + 'a,
+    {
+        self.terminals.insert(symbol, Box::new(constructor));
+        self
+    }
+
+    /// Registers a constructor for error tokens of a symbol. It receives the number of
+    /// skipped input tokens.
+    pub fn error<F>(&mut self, symbol: Symbol, constructor: F) -> &mut Self
+    where
+        F: FnMut(u32) -> V + 'a,
+    {
+        self.errors.insert(symbol, Box::new(constructor));
+        self
+    }
+
+    /// Registers a constructor for a nulling symbol.
+    pub fn nulling<F>(&mut self, symbol: Symbol, constructor: F) -> &mut Self
+    where
+        F: FnMut() -> V + 'a,
+    {
+        self.nulling.insert(symbol, Box::new(constructor));
+        self
+    }
+
+    /// Builds the tree rooted at `root` from a bocage.
+    pub fn build<'f, G>(
+        &mut self,
+        traverse: &mut bocage::Traverse<'f, G, L>,
+        root: NodeHandle,
+    ) -> Result<V, BuildError>
+    where
+        G: Borrow<InternalGrammar>,
+        L: LeafPayload,
+    {
+        let mut evaluation = Evaluation::new(self, Ambiguity::Error);
+        let values = evaluation.traverse(traverse, root);
+        into_tree(values)
+    }
+}
+
+impl<'a, V> Builder<'a, V> {
+    /// Builds the tree rooted at `root` from a compact bocage.
+    pub fn build_compact<'f, G>(
+        &mut self,
+        traverse: &mut compact_bocage::Traverse<'f, G>,
+        root: NodeHandle,
+    ) -> Result<V, BuildError>
+    where
+        G: Borrow<InternalGrammar>,
+    {
+        let mut evaluation = Evaluation::new(self, Ambiguity::Error);
+        let values = evaluation.traverse_compact(traverse, root);
+        into_tree(values)
+    }
+}
+
+fn into_tree<V>(
+    values: Result<Vec<Result<V, BuildError>>, AmbiguityError>,
+) -> Result<V, BuildError> {
+    let mut values = values.map_err(BuildError::Ambiguous)?;
+    values.pop().unwrap_or(Err(BuildError::NoTree))
+}
+
+impl<'a, V, L> Default for Builder<'a, V, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b, V, L> Evaluator<L> for &'b mut Builder<'a, V, L> {
+    type Value = Result<V, BuildError>;
+
+    fn leaf(&mut self, symbol: Symbol, value: L) -> Self::Value {
+        match self.terminals.get_mut(&symbol) {
+            Some(constructor) => Ok(constructor(value)),
+            None => Err(BuildError::MissingTerminal(symbol)),
+        }
+    }
+
+    fn error(&mut self, symbol: Symbol, skipped: u32) -> Self::Value {
+        match self.errors.get_mut(&symbol) {
+            Some(constructor) => Ok(constructor(skipped)),
+            None => Err(BuildError::MissingError(symbol)),
+        }
+    }
+
+    fn rule(&mut self, action: u32, args: &[&Self::Value]) -> Self::Value {
+        let mut values = Vec::with_capacity(args.len());
+        for &arg in args {
+            match *arg {
+                Ok(ref value) => values.push(value),
+                Err(ref error) => return Err(error.clone()),
+            }
+        }
+        match self.rules.get_mut(&action) {
+            Some(constructor) => Ok(constructor(&values[..])),
+            None => Err(BuildError::MissingRule(action)),
+        }
+    }
+
+    fn nulling(&mut self, symbol: Symbol, values: &mut Vec<Self::Value>) {
+        let value = match self.nulling.get_mut(&symbol) {
+            Some(constructor) => Ok(constructor()),
+            None => Err(BuildError::MissingNulling(symbol)),
+        };
+        values.push(value);
+    }
+}
+
+impl BuildError {
+    /// Returns a value that displays the error with symbol names taken from the grammar.
+    pub fn display<'a>(&'a self, grammar: &'a InternalGrammar) -> DisplayBuildError<'a> {
+        DisplayBuildError {
+            error: self,
+            grammar: Some(grammar),
+        }
+    }
+}
+
+/// Displays a build error with symbol names.
+pub struct DisplayBuildError<'a> {
+    error: &'a BuildError,
+    grammar: Option<&'a InternalGrammar>,
+}
+
+impl<'a> DisplayBuildError<'a> {
+    fn symbol(&self, f: &mut fmt::Formatter, symbol: Symbol) -> fmt::Result {
+        match self.grammar {
+            Some(grammar) => write!(f, "{}", grammar.display_symbol(symbol)),
+            None => write!(f, "{}", symbol.usize()),
+        }
+    }
+}
+
+impl<'a> fmt::Display for DisplayBuildError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self.error {
+            BuildError::MissingRule(rule) => write!(f, "no constructor for rule {}", rule),
+            BuildError::MissingTerminal(symbol) => {
+                write!(f, "no constructor for terminal ")?;
+                self.symbol(f, symbol)
+            }
+            BuildError::MissingError(symbol) => {
+                write!(f, "no constructor for error token ")?;
+                self.symbol(f, symbol)
+            }
+            BuildError::MissingNulling(symbol) => {
+                write!(f, "no constructor for nulling symbol ")?;
+                self.symbol(f, symbol)
+            }
+            BuildError::Ambiguous(ref error) => match self.grammar {
+                Some(grammar) => error.display(grammar).fmt(f),
+                None => error.fmt(f),
+            },
+            BuildError::NoTree => write!(f, "the root has no value"),
+        }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        DisplayBuildError {
+            error: self,
+            grammar: None,
+        }
+        .fmt(f)
+    }
+}
+
+impl Error for BuildError {}
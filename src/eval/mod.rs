@@ -0,0 +1,273 @@
+pub mod builder;
+
+pub use self::builder::{BuildError, Builder, DisplayBuildError};
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+use cfg::Symbol;
+
+use forest::bocage::traverse as bocage;
+use forest::bocage::LeafPayload;
+use forest::compact_bocage::traverse as compact_bocage;
+use forest::node_handle::NodeHandle;
+use grammar::InternalGrammar;
+
+/// Computes values of forest nodes. Tokens carry values of type `L`.
+pub trait Evaluator<L = u32> {
+    type Value;
+
+    /// Evaluates a token with its value.
+    fn leaf(&mut self, symbol: Symbol, value: L) -> Self::Value;
+
+    /// Evaluates the rule with the given external id for one combination of arguments.
+    fn rule(&mut self, action: u32, args: &[&Self::Value]) -> Self::Value;
+
+    /// Pushes the values of a nulling symbol.
+    fn nulling(&mut self, symbol: Symbol, values: &mut Vec<Self::Value>);
+
+    /// Evaluates an error token that stands for `skipped` input tokens.
+    fn error(&mut self, symbol: Symbol, skipped: u32) -> Self::Value;
+}
+
+/// Determines what happens to a node with more than one value.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Ambiguity {
+    /// Only the first value is kept. Remaining alternatives are not evaluated.
+    First,
+    /// Values of all derivations are kept.
+    All,
+    /// The evaluation fails.
+    Error,
+}
+
+/// An error returned when a node has more than one value and the evaluation is set to
+/// fail on ambiguity.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AmbiguityError {
+    /// The ambiguous node.
+    pub handle: NodeHandle,
+    /// The node's symbol.
+    pub symbol: Symbol,
+    /// The number of values.
+    pub count: usize,
+}
+
+/// Evaluates a forest bottom-up. Values are kept for every node until the evaluation ends.
+pub struct Evaluation<E, L = u32>
+where
+    E: Evaluator<L>,
+{
+    pub evaluator: E,
+    ambiguity: Ambiguity,
+    results: BTreeMap<NodeHandle, Vec<E::Value>>,
+    values: Vec<E::Value>,
+    marker: PhantomData<L>,
+}
+
+impl<E, L> Evaluation<E, L>
+where
+    E: Evaluator<L>,
+{
+    pub fn new(evaluator: E, ambiguity: Ambiguity) -> Self {
+        Evaluation {
+            evaluator,
+            ambiguity,
+            results: BTreeMap::new(),
+            values: vec![],
+            marker: PhantomData,
+        }
+    }
+
+    /// Evaluates the nodes of a bocage and returns the values of `root`. On error, the
+    /// traversal is left unfinished.
+    pub fn traverse<'f, G>(
+        &mut self,
+        traverse: &mut bocage::Traverse<'f, G, L>,
+        root: NodeHandle,
+    ) -> Result<Vec<E::Value>, AmbiguityError>
+    where
+        G: Borrow<InternalGrammar>,
+        L: LeafPayload,
+    {
+        self.results.clear();
+        self.values.clear();
+        while let Some(mut item) = traverse.next_node() {
+            match item.item {
+                bocage::SumHandle(ref mut products) => {
+                    while let Some(product) = products.next_product() {
+                        self.product(product.action, product.factor_handles);
+                        if self.ambiguity == Ambiguity::First && !self.values.is_empty() {
+                            break;
+                        }
+                    }
+                }
+                bocage::NullingHandle => {
+                    self.evaluator.nulling(item.symbol, &mut self.values);
+                }
+                bocage::LeafHandle(ref value) | bocage::VirtualHandle(ref value) => {
+                    let value = self.evaluator.leaf(item.symbol, value.clone());
+                    self.values.push(value);
+                }
+                bocage::ErrorHandle(skipped) => {
+                    let value = self.evaluator.error(item.symbol, skipped);
+                    self.values.push(value);
+                }
+            }
+            self.end_node(item.handle(), item.symbol)?;
+            item.end_evaluation();
+        }
+        Ok(self.results.remove(&root).unwrap_or_default())
+    }
+
+    // Evaluates a product for every combination of its factors' values.
+    fn product(&mut self, action: u32, factors: &[(Symbol, NodeHandle)]) {
+        let Evaluation {
+            ref mut evaluator,
+            ref results,
+            ref mut values,
+            ambiguity,
+            ..
+        } = *self;
+        let factors: Vec<&[E::Value]> = factors
+            .iter()
+            .map(|&(_sym, handle)| &results[&handle][..])
+            .collect();
+        if factors.iter().any(|values| values.is_empty()) {
+            return;
+        }
+        let mut indices = vec![0; factors.len()];
+        let mut args = Vec::with_capacity(factors.len());
+        loop {
+            args.clear();
+            args.extend(factors.iter().zip(&indices).map(|(values, &i)| &values[i]));
+            values.push(evaluator.rule(action, &args[..]));
+            if ambiguity == Ambiguity::First {
+                return;
+            }
+            // Move on to the next combination, with the first factor changing fastest.
+            let mut advanced = false;
+            for (i, factor) in indices.iter_mut().zip(&factors) {
+                *i += 1;
+                if *i < factor.len() {
+                    advanced = true;
+                    break;
+                }
+                *i = 0;
+            }
+            if !advanced {
+                return;
+            }
+        }
+    }
+
+    fn end_node(&mut self, handle: NodeHandle, symbol: Symbol) -> Result<(), AmbiguityError> {
+        match self.ambiguity {
+            Ambiguity::First => {
+                self.values.truncate(1);
+            }
+            Ambiguity::Error if self.values.len() > 1 => {
+                return Err(AmbiguityError {
+                    handle,
+                    symbol,
+                    count: self.values.len(),
+                });
+            }
+            _ => {}
+        }
+        let values = mem::take(&mut self.values);
+        self.results.insert(handle, values);
+        Ok(())
+    }
+}
+
+impl<E> Evaluation<E>
+where
+    E: Evaluator,
+{
+    /// Evaluates the nodes of a compact bocage and returns the values of `root`. On error,
+    /// the traversal is left unfinished.
+    pub fn traverse_compact<'f, G>(
+        &mut self,
+        traverse: &mut compact_bocage::Traverse<'f, G>,
+        root: NodeHandle,
+    ) -> Result<Vec<E::Value>, AmbiguityError>
+    where
+        G: Borrow<InternalGrammar>,
+    {
+        self.results.clear();
+        self.values.clear();
+        while let Some(mut item) = traverse.next_node() {
+            match item.item {
+                compact_bocage::SumHandle(ref mut products) => {
+                    while let Some(product) = products.next_product() {
+                        self.product(product.action, product.factors);
+                        if self.ambiguity == Ambiguity::First && !self.values.is_empty() {
+                            break;
+                        }
+                    }
+                }
+                compact_bocage::NullingHandle => {
+                    self.evaluator.nulling(item.symbol, &mut self.values);
+                }
+                compact_bocage::LeafHandle(value) | compact_bocage::VirtualHandle(value) => {
+                    let value = self.evaluator.leaf(item.symbol, value);
+                    self.values.push(value);
+                }
+                compact_bocage::ErrorHandle(skipped) => {
+                    let value = self.evaluator.error(item.symbol, skipped);
+                    self.values.push(value);
+                }
+            }
+            self.end_node(item.handle(), item.symbol)?;
+            item.end_evaluation();
+        }
+        Ok(self.results.remove(&root).unwrap_or_default())
+    }
+}
+
+impl AmbiguityError {
+    /// Returns a value that displays the error with symbol names taken from the grammar.
+    pub fn display<'a>(&'a self, grammar: &'a InternalGrammar) -> DisplayAmbiguityError<'a> {
+        DisplayAmbiguityError {
+            error: self,
+            grammar: Some(grammar),
+        }
+    }
+}
+
+/// Displays an ambiguity error with symbol names.
+pub struct DisplayAmbiguityError<'a> {
+    error: &'a AmbiguityError,
+    grammar: Option<&'a InternalGrammar>,
+}
+
+impl<'a> fmt::Display for DisplayAmbiguityError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ambiguous node for symbol ")?;
+        match self.grammar {
+            Some(grammar) => {
+                let symbol = grammar.to_external(self.error.symbol);
+                write!(f, "{}", grammar.display_symbol(symbol))?;
+            }
+            None => write!(f, "{}", self.error.symbol.usize())?,
+        }
+        write!(f, " with {} values", self.error.count)
+    }
+}
+
+impl fmt::Display for AmbiguityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        DisplayAmbiguityError {
+            error: self,
+            grammar: None,
+        }
+        .fmt(f)
+    }
+}
+
+impl Error for AmbiguityError {}
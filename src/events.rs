@@ -5,7 +5,7 @@ use bit_matrix;
 use cfg::symbol::Symbol;
 
 use forest::Forest;
-use grammar::{ExternalDottedRule, Event};
+use grammar::{ExternalDottedRule, Event, InternalGrammar};
 use item::Item;
 use recognizer::Recognizer;
 
@@ -56,6 +56,11 @@ pub struct ExpectedTerminals<'a, N: 'a> {
     rhs1: &'a [Option<Symbol>],
 }
 
+pub struct ExpectedExternalTerminals<'a> {
+    predicted: PredictedSymbols<'a>,
+    grammar: &'a InternalGrammar,
+}
+
 impl<'a> Iterator for PredictedSymbols<'a> {
     type Item = Symbol;
 
@@ -152,6 +157,17 @@ impl<'a, N> Iterator for ExpectedTerminals<'a, N> {
     }
 }
 
+impl<'a> Iterator for ExpectedExternalTerminals<'a> {
+    type Item = Symbol;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let grammar = self.grammar;
+        self.predicted
+            .find(|&symbol| grammar.is_terminal(symbol))
+            .map(|symbol| grammar.to_external(symbol))
+    }
+}
+
 impl<'g, F> Recognizer<'g, F>
     where F: Forest,
 {
@@ -197,6 +213,14 @@ impl<'g, F> Recognizer<'g, F>
             rhs1: self.grammar.rhs1(),
         }
     }
+
+    /// Accesses external terminals that can be scanned at the current location.
+    pub fn expected_external_terminals(&self) -> ExpectedExternalTerminals<'_> {
+        ExpectedExternalTerminals {
+            predicted: self.predicted_symbols(),
+            grammar: self.grammar,
+        }
+    }
 }
 
 #[test]
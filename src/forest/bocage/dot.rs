@@ -0,0 +1,222 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::io::{self, Write};
+use std::iter;
+
+use bit_vec::BitVec;
+
+use forest::bocage::node::Node::{self, *};
+use forest::bocage::{Bocage, LeafPayload};
+use forest::dot::{DotId, DotWriter};
+use forest::node_handle::NodeHandle;
+use grammar::InternalGrammar;
+
+impl<G, V> Bocage<G, V>
+where
+    G: Borrow<InternalGrammar>,
+    V: LeafPayload + fmt::Debug,
+{
+    /// Writes the forest rooted at `root` in the Graphviz dot language. Products of
+    /// internal rules are hidden, and their factors are shown in their place.
+    pub fn write_dot<W: Write>(&self, root: NodeHandle, out: &mut W) -> io::Result<()> {
+        self.write_dot_with(root, DotWriter::new(out, self.grammar.borrow(), false)?)
+    }
+
+    /// Writes the forest rooted at `root` in the Graphviz dot language, including
+    /// products of internal rules.
+    pub fn write_dot_with_transparent<W: Write>(
+        &self,
+        root: NodeHandle,
+        out: &mut W,
+    ) -> io::Result<()> {
+        self.write_dot_with(root, DotWriter::new(out, self.grammar.borrow(), true)?)
+    }
+
+    fn write_dot_with<W: Write>(&self, root: NodeHandle, mut dot: DotWriter<W>) -> io::Result<()> {
+        let mut visited = BitVec::from_elem(self.graph.len(), false);
+        let mut stack = vec![root];
+        while let Some(handle) = stack.pop() {
+            if visited[handle.usize()] {
+                continue;
+            }
+            visited.set(handle.usize(), true);
+            let id = DotId::node(handle);
+            match self.graph[handle.usize()].expand() {
+                Sum { nonterminal, .. } => {
+                    dot.sum(id, nonterminal)?;
+                    for (i, summand) in Self::summands(&self.graph, handle).iter().enumerate() {
+                        let summand = summand.expand();
+                        if self.is_hidden(summand, &dot) {
+                            continue;
+                        }
+                        let summand_id = DotId::part(handle, i as u32);
+                        dot.edge(id, summand_id)?;
+                        self.write_dot_product(summand_id, summand, &mut dot, &mut stack)?;
+                    }
+                }
+                product @ Product { .. } => {
+                    self.write_dot_product(id, product, &mut dot, &mut stack)?;
+                }
+                LeoExpanded { last, .. } => {
+                    let product = self.graph[last.usize()].expand();
+                    self.write_dot_product(id, product, &mut dot, &mut stack)?;
+                }
+                Leo { chain, bottom } => {
+                    self.write_dot_leo(handle, chain, bottom, &mut dot, &mut stack)?;
+                }
+                NullingLeaf { symbol } => {
+                    dot.nulling(id, symbol)?;
+                }
+                Evaluated { symbol, values } => {
+                    dot.leaf(id, symbol, &V::load(values, &self.leaves))?;
+                }
+                Error { symbol, skipped } => {
+                    dot.error(id, symbol, skipped)?;
+                }
+                Virtual { symbol, values } => {
+                    dot.virtual_leaf(id, symbol, &V::load(values, &self.leaves))?;
+                }
+                LeoLink { .. } => unreachable!(),
+            }
+        }
+        dot.finish()
+    }
+
+    // Writes the products of a Leo chain as if it was expanded. The topmost product
+    // stands for the Leo node.
+    fn write_dot_leo<W: Write>(
+        &self,
+        handle: NodeHandle,
+        chain: NodeHandle,
+        bottom: NodeHandle,
+        dot: &mut DotWriter<W>,
+        stack: &mut Vec<NodeHandle>,
+    ) -> io::Result<()> {
+        let mut links = vec![];
+        let mut link = Some(chain);
+        while let Some(link_handle) = link {
+            if let LeoLink {
+                dot: action,
+                left_factor,
+                up,
+            } = self.graph[link_handle.usize()].expand()
+            {
+                links.push((action, left_factor));
+                link = up;
+            } else {
+                unreachable!()
+            }
+        }
+        let mut right = vec![];
+        self.push_dot_factor(bottom, dot, &mut right);
+        stack.extend(right.iter().cloned());
+        let mut right: Vec<_> = right.into_iter().map(DotId::node).collect();
+        for (i, &(action, left_factor)) in links.iter().enumerate() {
+            let mut factors = vec![];
+            self.push_dot_factor(left_factor, dot, &mut factors);
+            stack.extend(factors.iter().cloned());
+            let mut factors: Vec<_> = factors.into_iter().map(DotId::node).collect();
+            factors.append(&mut right);
+            let is_top = i + 1 == links.len();
+            if !is_top && !dot.show_transparent && self.is_transparent(action) {
+                right = factors;
+                continue;
+            }
+            let id = if is_top {
+                DotId::node(handle)
+            } else {
+                DotId::part(handle, i as u32)
+            };
+            dot.product(id, self.grammar.borrow().external_origin(action))?;
+            for factor in factors {
+                dot.edge(id, factor)?;
+            }
+            right.push(id);
+        }
+        Ok(())
+    }
+
+    fn write_dot_product<W: Write>(
+        &self,
+        id: DotId,
+        product: Node,
+        dot: &mut DotWriter<W>,
+        stack: &mut Vec<NodeHandle>,
+    ) -> io::Result<()> {
+        if let Product {
+            action,
+            left_factor,
+            right_factor,
+        } = product
+        {
+            let origin = if self.is_transparent(action) {
+                None
+            } else {
+                self.grammar.borrow().external_origin(action)
+            };
+            dot.product(id, origin)?;
+            let mut factors = vec![];
+            let (left, right) = self.with_nulling_factor(action, left_factor, right_factor);
+            for factor in iter::once(left).chain(right) {
+                self.push_dot_factor(factor, dot, &mut factors);
+            }
+            for factor in factors {
+                dot.edge(id, DotId::node(factor))?;
+                stack.push(factor);
+            }
+        }
+        Ok(())
+    }
+
+    // Pushes a factor, or factors of a hidden product in its place.
+    fn push_dot_factor<W>(
+        &self,
+        factor: NodeHandle,
+        dot: &DotWriter<W>,
+        factors: &mut Vec<NodeHandle>,
+    ) {
+        let node = self.graph[factor.usize()].expand();
+        if let Product {
+            action,
+            left_factor,
+            right_factor,
+        } = node
+        {
+            if self.is_hidden(node, dot) {
+                let (left, right) = self.with_nulling_factor(action, left_factor, right_factor);
+                for factor in iter::once(left).chain(right) {
+                    self.push_dot_factor(factor, dot, factors);
+                }
+                return;
+            }
+        }
+        factors.push(factor);
+    }
+
+    fn is_hidden<W>(&self, node: Node, dot: &DotWriter<W>) -> bool {
+        match node {
+            Product { action, .. } => !dot.show_transparent && self.is_transparent(action),
+            _ => false,
+        }
+    }
+
+    // Adds an omitted nulling factor, as `mark_alive` does.
+    fn with_nulling_factor(
+        &self,
+        action: u32,
+        left_factor: NodeHandle,
+        right_factor: Option<NodeHandle>,
+    ) -> (NodeHandle, Option<NodeHandle>) {
+        if right_factor.is_none() {
+            if let Some((sym, dir)) = self.grammar.borrow().nulling(action) {
+                let nulling = NodeHandle::nulling(sym);
+                return if dir {
+                    (left_factor, Some(nulling))
+                } else {
+                    (nulling, Some(left_factor))
+                };
+            }
+        }
+        (left_factor, right_factor)
+    }
+}
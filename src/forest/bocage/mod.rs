@@ -1,33 +1,79 @@
+mod dot;
 pub mod node;
 pub mod order;
 pub mod traverse;
 
 use std::borrow::Borrow;
 use std::hint;
+use std::iter;
+use std::ops::Range;
 
 use bit_vec::BitVec;
 use cfg::symbol::Symbol;
 use ref_slice::ref_slice;
 
 use forest::node_handle::NodeHandle;
+use forest::tree::{self, TreeCount};
 use forest::Forest;
-use grammar::InternalGrammar;
+use grammar::{InternalGrammar, MAX_NULLING_SYMBOLS};
 use item::CompletedItem;
+use recognizer::Recognizer;
 
 use self::node::Node::*;
 use self::node::{CompactNode, Node, NULL_ACTION};
-use self::order::Order;
+use self::order::{filter_products, retain, NullOrder, Order};
 
-pub struct Bocage<G> {
+pub struct Bocage<G, V = u32> {
     pub(crate) graph: Vec<CompactNode>,
     pub(crate) gc: MarkAndSweep,
     pub(crate) grammar: G,
     pub(crate) summand_count: u32,
+    // Token values that don't fit in leaf nodes.
+    pub(crate) leaves: Vec<V>,
 }
 
+/// A value carried by tokens of a `Bocage`. Leaf nodes keep 32 bits for it. A `u32` is
+/// kept in place, and values of other types are kept in a side table by default. Other
+/// token types implement the trait with an empty `impl`.
+pub trait LeafPayload: Clone {
+    /// Stores the value and returns the number kept in its leaf node.
+    #[inline]
+    fn store(self, table: &mut Vec<Self>) -> u32 {
+        table.push(self);
+        table.len() as u32 - 1
+    }
+
+    /// Returns the value for the number kept in its leaf node.
+    #[inline]
+    fn load(stored: u32, table: &[Self]) -> Self {
+        table[stored as usize].clone()
+    }
+}
+
+impl LeafPayload for u32 {
+    #[inline]
+    fn store(self, _table: &mut Vec<u32>) -> u32 {
+        self
+    }
+
+    #[inline]
+    fn load(stored: u32, _table: &[u32]) -> u32 {
+        stored
+    }
+}
+
+impl LeafPayload for usize {}
+impl LeafPayload for u64 {}
+impl LeafPayload for i32 {}
+impl LeafPayload for i64 {}
+impl LeafPayload for char {}
+impl LeafPayload for String {}
+impl LeafPayload for &str {}
+impl LeafPayload for Range<usize> {}
+
 pub(crate) struct MarkAndSweep {
     pub(crate) liveness: BitVec,
-    // List for DFS and/or maybe relocation of stuff in the future.
+    // List for DFS.
     pub(crate) dfs: Vec<NodeHandle>,
 }
 
@@ -36,10 +82,25 @@ where
     G: Borrow<InternalGrammar>,
 {
     pub fn new(grammar: G) -> Self {
-        Self::with_capacities(grammar, 1024, 32)
+        Self::with_payload(grammar)
     }
 
     pub fn with_capacities(grammar: G, graph_cap: usize, dfs_cap: usize) -> Self {
+        Self::with_payload_capacities(grammar, graph_cap, dfs_cap)
+    }
+}
+
+impl<G, V> Bocage<G, V>
+where
+    G: Borrow<InternalGrammar>,
+    V: LeafPayload,
+{
+    /// Creates a bocage whose tokens carry values of type `V`.
+    pub fn with_payload(grammar: G) -> Self {
+        Self::with_payload_capacities(grammar, 1024, 32)
+    }
+
+    pub fn with_payload_capacities(grammar: G, graph_cap: usize, dfs_cap: usize) -> Self {
         let mut result = Bocage {
             graph: Vec::with_capacity(graph_cap),
             gc: MarkAndSweep {
@@ -48,6 +109,7 @@ where
             },
             grammar,
             summand_count: 0,
+            leaves: vec![],
         };
         result.initialize_nulling();
         result
@@ -58,7 +120,7 @@ where
         // self.nulling_leaf_count = self.nulling_symbol_count();
         let nulling_leaf_count = self.nulling_symbol_count();
         // Ensure that `max` is not ridiculously large.
-        assert!(nulling_leaf_count < (1 << 20), "invalid nullable symbol");
+        assert!(nulling_leaf_count < MAX_NULLING_SYMBOLS, "invalid nullable symbol");
         self.graph.extend((0..=nulling_leaf_count).map(|i| {
             NullingLeaf {
                 symbol: Symbol::from(i),
@@ -81,21 +143,182 @@ where
         self.grammar.borrow().max_nulling_symbol().unwrap_or(0)
     }
 
+    /// Marks nodes reachable from `root`, letting the order remove alternatives. A node that
+    /// is left without alternatives is removed along with products that have it as a factor.
+    /// When the order removes every tree, `root` is left unmarked.
     #[inline]
-    pub fn mark_alive<O: Order>(&mut self, root: NodeHandle, mut order: O) {
-        self.gc.liveness.clear();
+    pub fn mark_alive<O: Order>(&mut self, root: NodeHandle, order: O) {
+        self.mark_alive_from(iter::once(root), order);
+    }
+
+    fn mark_alive_from<I, O>(&mut self, roots: I, mut order: O)
+    where
+        I: IntoIterator<Item = NodeHandle>,
+        O: Order,
+    {
+        // Clearing a bit vector keeps its length.
+        self.gc.liveness.truncate(0);
         self.gc.liveness.grow(self.graph.len(), false);
-        self.gc.dfs.push(root);
+        self.gc.dfs.extend(roots);
+        let mut removed = vec![];
         while let Some(node) = self.gc.dfs.pop() {
             self.gc.liveness.set(node.usize(), true);
-            let summands = Bocage::<G>::summands(&self.graph, node);
+            if let Leo { chain, bottom } = self.graph[node.usize()].expand() {
+                self.expand_leo(node, chain, bottom);
+            }
+            if let LeoExpanded { first, last } = self.graph[node.usize()].expand() {
+                self.gc.dfs_queue_leo_factors(&self.graph, first, last);
+                continue;
+            }
+            let summands = Self::summands(&self.graph, node);
+            let count = summands.len();
             let summands = order.sum(summands);
             for summand in summands {
                 self.postprocess_product_tree_node(summand);
-                // TODO: use order for products.
+            }
+            let summands = filter_products(&mut order, self.grammar.borrow(), summands);
+            if summands.is_empty() {
+                removed.push(node);
+                continue;
+            }
+            if summands.len() < count {
+                if let Sum { nonterminal, .. } = self.graph[node.usize()].expand() {
+                    self.set(
+                        node,
+                        Sum {
+                            nonterminal,
+                            count: summands.len() as u32,
+                        },
+                    );
+                }
+            }
+            for summand in summands {
                 self.gc.dfs_queue_factors(summand);
             }
         }
+        if !removed.is_empty() {
+            self.remove_with_factors(removed);
+        }
+    }
+
+    /// Unmarks removed nodes and nodes that are left without alternatives once products
+    /// that refer to them are removed. Factors precede the nodes that refer to them, so
+    /// a single pass over marked nodes is enough.
+    fn remove_with_factors(&mut self, removed: Vec<NodeHandle>) {
+        let mut dead = BitVec::from_elem(self.graph.len(), false);
+        for node in removed {
+            dead.set(node.usize(), true);
+        }
+        for i in 0..self.graph.len() {
+            if dead[i] || !self.gc.liveness[i] {
+                continue;
+            }
+            let node = NodeHandle(i as u32);
+            let has_dead_factor = |alternative: &CompactNode| match alternative.expand() {
+                Product {
+                    left_factor,
+                    right_factor,
+                    ..
+                } => iter::once(left_factor)
+                    .chain(right_factor)
+                    .any(|factor| dead[factor.usize()]),
+                _ => false,
+            };
+            let alive = match self.graph[i].expand() {
+                Sum { nonterminal, count } => {
+                    let summands = Self::summands(&self.graph, node);
+                    let summands = retain(summands, |alternative| !has_dead_factor(alternative));
+                    if !summands.is_empty() && summands.len() < count as usize {
+                        self.set(
+                            node,
+                            Sum {
+                                nonterminal,
+                                count: summands.len() as u32,
+                            },
+                        );
+                    }
+                    !summands.is_empty()
+                }
+                LeoExpanded { first, last } => {
+                    let products = &self.graph[first.usize()..=last.usize()];
+                    products.iter().all(|product| match product.expand() {
+                        Product {
+                            left_factor,
+                            right_factor,
+                            ..
+                        } => iter::once(left_factor)
+                            .chain(right_factor)
+                            .all(|factor| factor >= first || !dead[factor.usize()]),
+                        _ => true,
+                    })
+                }
+                _ => !has_dead_factor(&self.graph[i]),
+            };
+            if !alive {
+                dead.set(i, true);
+                self.gc.liveness.set(i, false);
+            }
+        }
+    }
+
+    /// Returns the number of trees rooted at `root`. Call it before the traversal, which
+    /// overwrites nodes.
+    pub fn count_trees(&self, root: NodeHandle) -> TreeCount {
+        tree::count_trees(root, self.graph.len(), |node, alternatives| {
+            for summand in Self::summands(&self.graph, node) {
+                alternatives.push(match summand.expand() {
+                    Product {
+                        left_factor,
+                        right_factor,
+                        ..
+                    } => [Some(left_factor), right_factor],
+                    LeoLink {
+                        left_factor, up, ..
+                    } => [Some(left_factor), up],
+                    Leo { chain, bottom } => [Some(chain), Some(bottom)],
+                    LeoExpanded { last, .. } => [Some(last), None],
+                    NullingLeaf { .. } | Evaluated { .. } | Error { .. } | Virtual { .. } => {
+                        [None, None]
+                    }
+                    Sum { .. } => unreachable!(),
+                });
+            }
+        })
+    }
+
+    /// Expands a Leo chain into products that are placed at the end of the graph. The Leo
+    /// node keeps the range of these products. The traversal evaluates them in place of
+    /// the Leo node.
+    fn expand_leo(&mut self, node: NodeHandle, chain: NodeHandle, bottom: NodeHandle) {
+        let first = NodeHandle(self.graph.len() as u32);
+        let mut right_factor = bottom;
+        let mut link = Some(chain);
+        while let Some(handle) = link {
+            if let LeoLink {
+                dot,
+                left_factor,
+                up,
+            } = self.graph[handle.usize()].expand()
+            {
+                let product = NodeHandle(self.graph.len() as u32);
+                self.graph.push(
+                    Product {
+                        action: dot,
+                        left_factor,
+                        right_factor: Some(right_factor),
+                    }
+                    .compact(),
+                );
+                right_factor = product;
+                link = up;
+            } else {
+                unreachable!()
+            }
+        }
+        let last = right_factor;
+        self.set(node, LeoExpanded { first, last });
+        let grow = self.graph.len() - self.gc.liveness.len();
+        self.gc.liveness.grow(grow, false);
     }
 
     #[inline]
@@ -149,34 +372,194 @@ where
         action == NULL_ACTION || self.grammar.borrow().external_origin(action).is_none()
     }
 
-    // fn mark_and_sweep(&mut self, root: NodeHandle) {
-    //     self.mark_alive(root);
-    //     self.sweep_garbage();
-    //     self.update_nulling_leaf_count();
-    // }
-
-    // fn sweep_garbage(&mut self) {
-    //     let count = self.relocate_marked();
-    //     self.graph.truncate(count);
-    // }
-
-    // fn update_nulling_leaf_count(&mut self) {
-    //     let prev_count = self.nulling_leaf_count;
-    //     self.nulling_leaf_count = self.gc.liveness.iter().take(prev_count).filter(|x| x).count();
-    // }
-
-    // fn relocate_marked(&mut self) -> usize {
-    //     let mut destination = self.graph.iter();
-    //     let mut count = 0;
-    //     // ... TODO: relocate
-    //     for (alive, source) in self.gc.liveness.iter().zip(self.graph.iter()) {
-    //         if alive {
-    //             destination.next().unwrap().cell.set(*source);
-    //             count += 1;
-    //         }
-    //     }
-    //     count
-    // }
+    /// Marks nodes reachable from `root` and removes all other nodes. The remaining nodes
+    /// are moved to the front of the graph in their original order. Handles held outside
+    /// of the bocage must be updated with the returned relocation.
+    pub fn mark_and_sweep<O: Order>(&mut self, root: NodeHandle, order: O) -> Relocation {
+        self.mark_alive(root, order);
+        self.sweep_garbage()
+    }
+
+    /// Marks nodes reachable from any of the `roots` and removes all other nodes. Handles held
+    /// outside of the bocage must be updated with the returned relocation.
+    pub fn mark_and_sweep_from<I, O>(&mut self, roots: I, order: O) -> Relocation
+    where
+        I: IntoIterator<Item = NodeHandle>,
+        O: Order,
+    {
+        self.mark_alive_from(roots, order);
+        self.sweep_garbage()
+    }
+
+    fn sweep_garbage(&mut self) -> Relocation {
+        let relocation = self.relocate_marked();
+        let len = relocation.len as usize;
+        self.graph.truncate(len);
+        self.gc.liveness.truncate(len);
+        relocation
+    }
+
+    // Computes new positions of kept nodes, then moves them. Nodes only move towards
+    // the front, so they can be moved in place.
+    fn relocate_marked(&mut self) -> Relocation {
+        let kept = self.kept_nodes();
+        let mut map = Vec::with_capacity(kept.len());
+        let mut len = 0;
+        for keep in kept.iter() {
+            if keep {
+                map.push(len);
+                len += 1;
+            } else {
+                map.push(REMOVED);
+            }
+        }
+        let relocation = Relocation { map, len };
+        let mut leaf_count = 0;
+        for (old, keep) in kept.iter().enumerate() {
+            if !keep {
+                continue;
+            }
+            let new = relocation.map[old] as usize;
+            let node = match self.graph[old].expand() {
+                Product {
+                    action,
+                    left_factor,
+                    right_factor,
+                } => Product {
+                    action,
+                    left_factor: relocation.moved(left_factor),
+                    right_factor: right_factor.map(|factor| relocation.moved(factor)),
+                },
+                LeoExpanded { first, last } => LeoExpanded {
+                    first: relocation.moved(first),
+                    last: relocation.moved(last),
+                },
+                LeoLink {
+                    dot,
+                    left_factor,
+                    up,
+                } => LeoLink {
+                    dot,
+                    left_factor: relocation.moved(left_factor),
+                    up: up.map(|link| relocation.moved(link)),
+                },
+                Evaluated { symbol, values } if !self.leaves.is_empty() => {
+                    self.leaves.swap(leaf_count, values as usize);
+                    leaf_count += 1;
+                    Evaluated {
+                        symbol,
+                        values: leaf_count as u32 - 1,
+                    }
+                }
+                Virtual { symbol, values } if !self.leaves.is_empty() => {
+                    self.leaves.swap(leaf_count, values as usize);
+                    leaf_count += 1;
+                    Virtual {
+                        symbol,
+                        values: leaf_count as u32 - 1,
+                    }
+                }
+                Leo { .. } => unreachable!(),
+                node => node,
+            };
+            self.graph[new].set(node);
+            let alive = self.gc.liveness.get(old).unwrap_or(false);
+            self.gc.liveness.set(new, alive);
+        }
+        self.leaves.truncate(leaf_count);
+        relocation
+    }
+
+    // Nodes that survive garbage collection are nulling leaves, live nodes, summands
+    // of live sums and products of expanded Leo chains.
+    fn kept_nodes(&mut self) -> BitVec {
+        let len = self.graph.len();
+        let nulling_len = self.nulling_symbol_count() + 1;
+        let grow = len.saturating_sub(self.gc.liveness.len());
+        self.gc.liveness.grow(grow, false);
+        let mut kept = BitVec::from_elem(len, false);
+        let mut idx = 0;
+        while idx < len {
+            if idx < nulling_len || self.gc.liveness[idx] {
+                kept.set(idx, true);
+                match self.graph[idx].expand() {
+                    Sum { count, .. } => {
+                        for summand in idx + 1..=idx + count as usize {
+                            kept.set(summand, true);
+                        }
+                        idx += count as usize;
+                    }
+                    LeoExpanded { first, last } => {
+                        for product in first.usize()..=last.usize() {
+                            kept.set(product, true);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            idx += 1;
+        }
+        kept
+    }
+}
+
+const REMOVED: u32 = !0;
+
+/// New positions of nodes after garbage collection.
+#[derive(Clone, Debug)]
+pub struct Relocation {
+    map: Vec<u32>,
+    len: u32,
+}
+
+impl Relocation {
+    /// Returns the new handle of a node, or `None` if the node was removed.
+    pub fn get(&self, handle: NodeHandle) -> Option<NodeHandle> {
+        match self.map.get(handle.usize()) {
+            Some(&REMOVED) | None => None,
+            Some(&idx) => Some(NodeHandle(idx)),
+        }
+    }
+
+    /// Returns the number of nodes that were kept.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns whether all nodes were removed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn moved(&self, handle: NodeHandle) -> NodeHandle {
+        self.get(handle).expect("reference to a removed node")
+    }
+}
+
+impl<G, V> Bocage<G, V> {
+    /// Checks that every node refers to nodes within the graph, and that the summands of
+    /// every sum lie within it.
+    pub(crate) fn check_graph(&self) -> bool {
+        let len = self.graph.len();
+        let within = |handle: NodeHandle| handle.usize() < len;
+        self.graph
+            .iter()
+            .enumerate()
+            .all(|(pos, node)| match node.expand() {
+                Sum { count, .. } => count >= 1 && pos + (count as usize) < len,
+                Product {
+                    left_factor,
+                    right_factor,
+                    ..
+                } => within(left_factor) && right_factor.into_iter().all(within),
+                LeoLink {
+                    left_factor, up, ..
+                } => within(left_factor) && up.into_iter().all(within),
+                Leo { chain, bottom } => within(chain) && within(bottom),
+                LeoExpanded { first, last } => first.0 <= last.0 && within(last),
+                NullingLeaf { .. } | Evaluated { .. } | Error { .. } | Virtual { .. } => true,
+            })
+    }
 }
 
 impl MarkAndSweep {
@@ -197,15 +580,53 @@ impl MarkAndSweep {
                     self.dfs.push(left_factor);
                 }
             }
-            NullingLeaf { .. } | Evaluated { .. } => {}
-            Sum { .. } => unreachable!(),
+            // A link is marked when a recognizer's Leo item refers to it.
+            LeoLink {
+                left_factor, up, ..
+            } => {
+                for factor in iter::once(left_factor).chain(up) {
+                    if let Some(false) = self.liveness.get(factor.usize()) {
+                        self.dfs.push(factor);
+                    }
+                }
+            }
+            NullingLeaf { .. } | Evaluated { .. } | Error { .. } | Virtual { .. } => {}
+            Sum { .. } | Leo { .. } | LeoExpanded { .. } => unreachable!(),
+        }
+    }
+
+    /// Queues factors of products expanded from a Leo chain. The products themselves are
+    /// not marked, because they are reached only through their Leo node.
+    fn dfs_queue_leo_factors(
+        &mut self,
+        graph: &[CompactNode],
+        first: NodeHandle,
+        last: NodeHandle,
+    ) {
+        for product in &graph[first.usize()..=last.usize()] {
+            if let Product {
+                left_factor,
+                right_factor,
+                ..
+            } = product.expand()
+            {
+                let factors = iter::once(left_factor).chain(right_factor);
+                for factor in factors.filter(|&factor| factor < first) {
+                    if let Some(false) = self.liveness.get(factor.usize()) {
+                        self.dfs.push(factor);
+                    }
+                }
+            }
         }
     }
 }
 
-impl<G> Forest for Bocage<G> {
+impl<G, V> Forest for Bocage<G, V>
+where
+    V: LeafPayload,
+{
     type NodeRef = NodeHandle;
-    type LeafValue = u32;
+    type LeafValue = V;
 
     const FOREST_BYTES_PER_RECOGNIZER_BYTE: usize = 2;
 
@@ -257,7 +678,33 @@ impl<G> Forest for Bocage<G> {
         self.graph.push(
             Evaluated {
                 symbol: token,
-                values: value,
+                values: value.store(&mut self.leaves),
+            }
+            .compact(),
+        );
+        result
+    }
+
+    #[inline]
+    fn error(&mut self, token: Symbol, _pos: u32, skipped: u32) -> Self::NodeRef {
+        let result = NodeHandle(self.graph.len() as u32);
+        self.graph.push(
+            Error {
+                symbol: token,
+                skipped,
+            }
+            .compact(),
+        );
+        result
+    }
+
+    #[inline]
+    fn virtual_leaf(&mut self, token: Symbol, _pos: u32, value: Self::LeafValue) -> Self::NodeRef {
+        let result = NodeHandle(self.graph.len() as u32);
+        self.graph.push(
+            Virtual {
+                symbol: token,
+                values: value.store(&mut self.leaves),
             }
             .compact(),
         );
@@ -268,4 +715,93 @@ impl<G> Forest for Bocage<G> {
     fn nulling(&self, token: Symbol) -> Self::NodeRef {
         NodeHandle::nulling(token)
     }
+
+    #[inline]
+    fn leo_link(
+        &mut self,
+        dot: u32,
+        left: Self::NodeRef,
+        up: Option<Self::NodeRef>,
+    ) -> Self::NodeRef {
+        let result = NodeHandle(self.graph.len() as u32);
+        self.graph.push(
+            LeoLink {
+                dot,
+                left_factor: left,
+                up,
+            }
+            .compact(),
+        );
+        result
+    }
+
+    #[inline]
+    fn leo(&mut self, chain: Self::NodeRef, bottom: Self::NodeRef) -> Self::NodeRef {
+        let result = NodeHandle(self.graph.len() as u32);
+        self.graph.push(Leo { chain, bottom }.compact());
+        result
+    }
+
+    #[inline]
+    fn graph_len(&self) -> usize {
+        self.graph.len()
+    }
+
+    #[inline]
+    fn truncate_graph(&mut self, len: usize) {
+        if !self.leaves.is_empty() {
+            // Drop the values of removed leaves, which are stored in order.
+            let first_removed = self.graph[len..]
+                .iter()
+                .find_map(|node| match node.expand() {
+                    Evaluated { values, .. } | Virtual { values, .. } => Some(values),
+                    _ => None,
+                });
+            if let Some(index) = first_removed {
+                self.leaves.truncate(index as usize);
+            }
+        }
+        self.graph.truncate(len);
+    }
+}
+
+impl<'g, G, V> Recognizer<'g, Bocage<G, V>>
+where
+    G: Borrow<InternalGrammar>,
+    V: LeafPayload,
+{
+    /// Removes forest nodes that the chart doesn't refer to. Handles held by the chart are
+    /// updated, so that the parse can go on. Other handles must be updated with the returned
+    /// relocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a checkpoint is outstanding, because it refers to the forest's length.
+    pub fn collect_garbage(&mut self) -> Relocation {
+        assert_eq!(
+            self.checkpoint_depth, 0,
+            "can't collect garbage while a checkpoint is outstanding"
+        );
+        let roots = {
+            let medial = self.medial.iter().map(|item| item.node);
+            let complete = self.complete.iter().filter_map(|item| item.node);
+            let pending = self.pending.values().flatten().map(|token| token.node);
+            let leo = self.leo.iter().filter_map(|leo| leo.chain);
+            medial.chain(complete).chain(pending).chain(leo).collect::<Vec<_>>()
+        };
+        let relocation = self.forest.mark_and_sweep_from(roots, NullOrder::new());
+        for item in &mut self.medial {
+            item.node = relocation.moved(item.node);
+        }
+        for item in &mut self.complete {
+            item.node = item.node.map(|node| relocation.moved(node));
+        }
+        for token in self.pending.values_mut().flatten() {
+            token.node = relocation.moved(token.node);
+        }
+        for leo in &mut self.leo {
+            leo.chain = leo.chain.map(|chain| relocation.moved(chain));
+        }
+        relocation
+    }
 }
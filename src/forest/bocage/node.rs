@@ -1,5 +1,4 @@
 use std::cell::Cell;
-use std::hint;
 
 use cfg::symbol::Symbol;
 
@@ -32,6 +31,35 @@ pub enum Node {
         symbol: Symbol,
         values: u32,
     },
+    Error {
+        /// 12 bytes.
+        symbol: Symbol,
+        skipped: u32,
+    },
+    Virtual {
+        /// 12 bytes.
+        symbol: Symbol,
+        values: u32,
+    },
+    LeoLink {
+        /// 12 bytes.
+        /// A link of a Leo chain. Its right factor is left open.
+        dot: u32,
+        left_factor: NodeHandle,
+        up: Option<NodeHandle>,
+    },
+    Leo {
+        /// 12 bytes.
+        /// A Leo chain completed by the `bottom` node, which is yet to be expanded.
+        chain: NodeHandle,
+        bottom: NodeHandle,
+    },
+    LeoExpanded {
+        /// 12 bytes.
+        /// A Leo chain that was expanded into products between `first` and `last`.
+        first: NodeHandle,
+        last: NodeHandle,
+    },
 }
 
 #[derive(Clone)]
@@ -54,30 +82,33 @@ union CompactField {
     // leaf
     symbol: Symbol,
     values: u32,
+    kind: u32,
 
     // tag
     tag: u32,
 }
 
 #[derive(Copy, Clone)]
+#[repr(u32)]
 enum Tag {
     LeafTag = 0b00 << TAG_BIT,
     SumTag = 0b01 << TAG_BIT,
     ProductTag = 0b10 << TAG_BIT,
+    LeoLinkTag = 0b11 << TAG_BIT,
 }
 
 impl Tag {
     #[inline]
-    fn from_u32(n: u32) -> Option<Self> {
+    fn from_u32(n: u32) -> Self {
         let n = n & TAG_MASK;
         if n == LeafTag.to_u32() {
-            Some(LeafTag)
+            LeafTag
         } else if n == SumTag.to_u32() {
-            Some(SumTag)
+            SumTag
         } else if n == ProductTag.to_u32() {
-            Some(ProductTag)
+            ProductTag
         } else {
-            None
+            LeoLinkTag
         }
     }
 
@@ -87,6 +118,7 @@ impl Tag {
             LeafTag => 0b00 << TAG_BIT,
             SumTag => 0b01 << TAG_BIT,
             ProductTag => 0b10 << TAG_BIT,
+            LeoLinkTag => 0b11 << TAG_BIT,
         }
     }
 }
@@ -94,7 +126,12 @@ impl Tag {
 const TAG_BIT: usize = 30;
 const TAG_MASK: u32 = 0b11 << TAG_BIT;
 const NULL_VALUES: u32 = 0xFFFF_FFFF;
+const TOKEN_KIND: u32 = 0;
+const ERROR_KIND: u32 = 1;
+const VIRTUAL_KIND: u32 = 2;
 pub(super) const NULL_ACTION: u32 = !TAG_MASK;
+const LEO_ACTION: u32 = NULL_ACTION - 1;
+const LEO_EXPANDED_ACTION: u32 = NULL_ACTION - 2;
 
 impl Node {
     #[inline]
@@ -131,7 +168,42 @@ impl Node {
             Evaluated { symbol, values } => [
                 CompactField { symbol },
                 CompactField { values },
-                CompactField { tag: 0 },
+                CompactField { kind: TOKEN_KIND },
+            ],
+            Error { symbol, skipped } => [
+                CompactField { symbol },
+                CompactField { values: skipped },
+                CompactField { kind: ERROR_KIND },
+            ],
+            Virtual { symbol, values } => [
+                CompactField { symbol },
+                CompactField { values },
+                CompactField { kind: VIRTUAL_KIND },
+            ],
+            LeoLink {
+                dot,
+                left_factor,
+                up,
+            } => [
+                CompactField { action: dot },
+                CompactField {
+                    factor: left_factor,
+                },
+                CompactField {
+                    factor: up.unwrap_or(NULL_HANDLE),
+                },
+            ],
+            Leo { chain, bottom } => [
+                CompactField { action: LEO_ACTION },
+                CompactField { factor: chain },
+                CompactField { factor: bottom },
+            ],
+            LeoExpanded { first, last } => [
+                CompactField {
+                    action: LEO_EXPANDED_ACTION,
+                },
+                CompactField { factor: first },
+                CompactField { factor: last },
             ],
         };
         unsafe {
@@ -145,14 +217,32 @@ impl Node {
     #[inline]
     fn tag(&self) -> Tag {
         match self {
-            Product { .. } => ProductTag,
+            Product { .. } | Leo { .. } | LeoExpanded { .. } => ProductTag,
+            LeoLink { .. } => LeoLinkTag,
             Sum { .. } => SumTag,
-            NullingLeaf { .. } | Evaluated { .. } => LeafTag,
+            NullingLeaf { .. } | Evaluated { .. } | Error { .. } | Virtual { .. } => LeafTag,
         }
     }
 }
 
 impl CompactNode {
+    /// Returns the raw representation of the node.
+    pub(crate) fn to_words(&self) -> [u32; 3] {
+        let fields = self.cell.get();
+        unsafe { [fields[0].tag, fields[1].tag, fields[2].tag] }
+    }
+
+    /// Creates a node from its raw representation.
+    pub(crate) fn from_words(words: [u32; 3]) -> Self {
+        CompactNode {
+            cell: Cell::new([
+                CompactField { tag: words[0] },
+                CompactField { tag: words[1] },
+                CompactField { tag: words[2] },
+            ]),
+        }
+    }
+
     #[inline]
     pub(super) fn set(&self, node: Node) {
         self.cell.set(node.compact().cell.get());
@@ -169,6 +259,16 @@ impl CompactNode {
                         NullingLeaf {
                             symbol: fields[0].symbol,
                         }
+                    } else if fields[2].kind == ERROR_KIND {
+                        Error {
+                            symbol: fields[0].symbol,
+                            skipped: fields[1].values,
+                        }
+                    } else if fields[2].kind == VIRTUAL_KIND {
+                        Virtual {
+                            symbol: fields[0].symbol,
+                            values: fields[1].values,
+                        }
                     } else {
                         Evaluated {
                             symbol: fields[0].symbol,
@@ -176,10 +276,25 @@ impl CompactNode {
                         }
                     }
                 }
-                ProductTag => Product {
-                    action: fields[0].action,
+                ProductTag => match fields[0].action {
+                    LEO_ACTION => Leo {
+                        chain: fields[1].factor,
+                        bottom: fields[2].factor,
+                    },
+                    LEO_EXPANDED_ACTION => LeoExpanded {
+                        first: fields[1].factor,
+                        last: fields[2].factor,
+                    },
+                    action => Product {
+                        action,
+                        left_factor: fields[1].factor,
+                        right_factor: fields[2].factor.to_option(),
+                    },
+                },
+                LeoLinkTag => LeoLink {
+                    dot: fields[0].action,
                     left_factor: fields[1].factor,
-                    right_factor: fields[2].factor.to_option(),
+                    up: fields[2].factor.to_option(),
                 },
                 SumTag => Sum {
                     nonterminal: fields[0].nonterminal,
@@ -190,14 +305,6 @@ impl CompactNode {
     }
 }
 
-#[inline]
-unsafe fn unwrap_unchecked<T>(opt: Option<T>) -> T {
-    match opt {
-        Some(val) => val,
-        None => hint::unreachable_unchecked(),
-    }
-}
-
 #[inline]
 unsafe fn set_tag(fields: &mut [CompactField; 3], tag: Tag) {
     fields[0].tag |= tag.to_u32();
@@ -208,5 +315,5 @@ unsafe fn get_and_erase_tag(fields: &mut [CompactField; 3]) -> Tag {
     let &mut CompactField { ref mut tag } = &mut fields[0];
     let extract_tag = *tag;
     *tag = *tag & !TAG_MASK;
-    unwrap_unchecked(Tag::from_u32(extract_tag))
+    Tag::from_u32(extract_tag)
 }
@@ -1,19 +1,60 @@
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+
 use cfg::symbol::Symbol;
 
+use forest::node_handle::NodeHandle;
+use forest::Bocage;
+use grammar::InternalGrammar;
+
 use super::node::CompactNode;
+use super::node::Node::*;
 
 pub trait Order {
-    /// Apply the order to sum node alternatives.
+    /// Apply the order to sum node alternatives. The alternatives may be reordered in place.
+    /// The result must be a prefix of the alternatives. Alternatives outside of it are
+    /// removed from the sum.
     fn sum<'b>(&mut self, alternatives: &'b [CompactNode]) -> &'b [CompactNode] {
         alternatives
     }
 
-    /// Apply the order to product node factors.
+    /// Apply the order to product node factors, which are given as pairs of an internal
+    /// symbol and a node handle. Called for every alternative of a sum node that is kept
+    /// by `sum`. Returning the index of a factor removes the product from the sum.
     fn product(&mut self, _factors: &[(Symbol, u32)]) -> Option<usize> {
         None
     }
 }
 
+/// Lets the order remove sum alternatives through their factors. Returns the kept
+/// alternatives, which are moved to the front. They may be empty.
+pub(super) fn filter_products<'b, O: Order>(
+    order: &mut O,
+    grammar: &InternalGrammar,
+    alternatives: &'b [CompactNode],
+) -> &'b [CompactNode] {
+    if alternatives.len() <= 1 {
+        return alternatives;
+    }
+    let kept = retain(alternatives, |alt| match alt.expand() {
+        Product {
+            action,
+            left_factor,
+            right_factor,
+        } if action < grammar.num_rules() as u32 => {
+            let symbols = grammar.factor_symbols(action);
+            let factors: Vec<_> = symbols
+                .iter()
+                .zip(&[Some(left_factor), right_factor])
+                .filter_map(|(&sym, &factor)| Some((sym?, factor?.0)))
+                .collect();
+            order.product(&factors).is_none()
+        }
+        _ => true,
+    });
+    kept
+}
+
 #[derive(Default)]
 pub struct NullOrder;
 
@@ -24,3 +65,251 @@ impl NullOrder {
         NullOrder
     }
 }
+
+/// Integer costs of rules and tokens. The cost of a derivation is the sum of costs
+/// of its rules and tokens. Costs are zero unless set.
+#[derive(Clone, Debug, Default)]
+pub struct Costs {
+    rules: Vec<u32>,
+    tokens: Vec<u32>,
+}
+
+impl Costs {
+    pub fn new() -> Self {
+        Costs::default()
+    }
+
+    /// Sets the cost of a rule, which is identified by its external rule ID.
+    pub fn set_rule_cost(&mut self, rule: u32, cost: u32) {
+        set_cost(&mut self.rules, rule as usize, cost);
+    }
+
+    /// Sets the cost of a token. Error and virtual tokens have the cost of their symbol.
+    pub fn set_token_cost(&mut self, symbol: Symbol, cost: u32) {
+        set_cost(&mut self.tokens, symbol.usize(), cost);
+    }
+
+    pub fn rule_cost(&self, rule: u32) -> u32 {
+        self.rules.get(rule as usize).cloned().unwrap_or(0)
+    }
+
+    pub fn token_cost(&self, symbol: Symbol) -> u32 {
+        self.tokens.get(symbol.usize()).cloned().unwrap_or(0)
+    }
+}
+
+fn set_cost(costs: &mut Vec<u32>, idx: usize, cost: u32) {
+    if costs.len() <= idx {
+        costs.resize(idx + 1, 0);
+    }
+    costs[idx] = cost;
+}
+
+/// An order that keeps only the alternatives of minimal cost in every sum node.
+pub struct MinimalCostOrder {
+    // Costs of internal rules.
+    action_costs: Vec<u32>,
+    // Minimal costs of nodes.
+    node_costs: Vec<u32>,
+}
+
+impl MinimalCostOrder {
+    /// Computes minimal costs of all nodes in the bocage. Must be used before the bocage
+    /// is traversed.
+    pub fn new<G, V>(bocage: &Bocage<G, V>, costs: &Costs) -> Self
+    where
+        G: Borrow<InternalGrammar>,
+    {
+        let grammar = bocage.grammar.borrow();
+        let action_costs = (0..grammar.num_rules() as u32)
+            .map(|action| {
+                grammar
+                    .external_origin(action)
+                    .map_or(0, |rule| costs.rule_cost(rule))
+            })
+            .collect();
+        let mut order = MinimalCostOrder {
+            action_costs,
+            node_costs: Vec::with_capacity(bocage.graph.len()),
+        };
+        // Factors precede the nodes that refer to them, with the exception of expanded
+        // Leo chains.
+        for (pos, node) in bocage.graph.iter().enumerate() {
+            let cost = match node.expand() {
+                Sum { count, .. } => {
+                    let products = &bocage.graph[pos + 1..pos + 1 + count as usize];
+                    products
+                        .iter()
+                        .map(|product| order.product_cost(product))
+                        .min()
+                        .unwrap()
+                }
+                Product { .. } => order.product_cost(node),
+                NullingLeaf { .. } => 0,
+                Evaluated { symbol, .. } | Error { symbol, .. } | Virtual { symbol, .. } => {
+                    costs.token_cost(symbol)
+                }
+                LeoLink {
+                    dot,
+                    left_factor,
+                    up,
+                } => {
+                    let up_cost = up.map_or(0, |up| order.cost(up));
+                    order
+                        .action_cost(dot)
+                        .saturating_add(order.cost(left_factor))
+                        .saturating_add(up_cost)
+                }
+                Leo { chain, bottom } => order.cost(chain).saturating_add(order.cost(bottom)),
+                LeoExpanded { first, last } => {
+                    let products = &bocage.graph[first.usize()..=last.usize()];
+                    // The lowest product has the bottom of the chain as its right factor.
+                    let bottom_cost = match products[0].expand() {
+                        Product {
+                            right_factor: Some(bottom),
+                            ..
+                        } => order.cost(bottom),
+                        _ => unreachable!(),
+                    };
+                    products
+                        .iter()
+                        .fold(bottom_cost, |below, product| match product.expand() {
+                            Product {
+                                action,
+                                left_factor,
+                                ..
+                            } => order
+                                .action_cost(action)
+                                .saturating_add(order.cost(left_factor))
+                                .saturating_add(below),
+                            _ => unreachable!(),
+                        })
+                }
+            };
+            order.node_costs.push(cost);
+        }
+        order
+    }
+
+    /// Returns the minimal cost of a node.
+    pub fn cost(&self, node: NodeHandle) -> u32 {
+        self.node_costs[node.usize()]
+    }
+
+    fn action_cost(&self, action: u32) -> u32 {
+        self.action_costs.get(action as usize).cloned().unwrap_or(0)
+    }
+
+    fn product_cost(&self, product: &CompactNode) -> u32 {
+        match product.expand() {
+            Product {
+                action,
+                left_factor,
+                right_factor,
+            } => {
+                let right_cost = right_factor.map_or(0, |factor| self.cost(factor));
+                self.action_cost(action)
+                    .saturating_add(self.cost(left_factor))
+                    .saturating_add(right_cost)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Order for MinimalCostOrder {
+    fn sum<'b>(&mut self, alternatives: &'b [CompactNode]) -> &'b [CompactNode] {
+        if alternatives.len() <= 1 {
+            // Not a sum node.
+            return alternatives;
+        }
+        let min_cost = alternatives
+            .iter()
+            .map(|alt| self.product_cost(alt))
+            .min()
+            .unwrap();
+        retain(alternatives, |alt| self.product_cost(alt) == min_cost)
+    }
+}
+
+/// An order that keeps only the sum alternatives of the highest rank. Ranks of rules are
+/// set with `InternalGrammar::set_rule_rank`.
+pub struct RankOrder<'g> {
+    pub(crate) grammar: &'g InternalGrammar,
+}
+
+impl<'g> RankOrder<'g> {
+    pub fn new(grammar: &'g InternalGrammar) -> Self {
+        RankOrder { grammar }
+    }
+}
+
+impl<'g> Order for RankOrder<'g> {
+    fn sum<'b>(&mut self, alternatives: &'b [CompactNode]) -> &'b [CompactNode] {
+        if alternatives.len() <= 1 {
+            // Not a sum node.
+            return alternatives;
+        }
+        let max_rank = alternatives
+            .iter()
+            .map(|alt| rank(self.grammar, alt))
+            .max()
+            .unwrap();
+        retain(alternatives, |alt| rank(self.grammar, alt) == max_rank)
+    }
+}
+
+/// An order that keeps all sum alternatives, but puts them in the order of decreasing
+/// rank. Only the bocage can reorder alternatives.
+pub struct RankReorder<'g> {
+    grammar: &'g InternalGrammar,
+}
+
+impl<'g> RankReorder<'g> {
+    pub fn new(grammar: &'g InternalGrammar) -> Self {
+        RankReorder { grammar }
+    }
+}
+
+impl<'g> Order for RankReorder<'g> {
+    fn sum<'b>(&mut self, alternatives: &'b [CompactNode]) -> &'b [CompactNode] {
+        if alternatives.len() <= 1 {
+            // Not a sum node.
+            return alternatives;
+        }
+        let mut nodes: Vec<_> = alternatives
+            .iter()
+            .map(|alt| (rank(self.grammar, alt), alt.expand()))
+            .collect();
+        // The sort is stable.
+        nodes.sort_by_key(|&(rank, _)| Reverse(rank));
+        for (alt, (_rank, node)) in alternatives.iter().zip(nodes) {
+            alt.set(node);
+        }
+        alternatives
+    }
+}
+
+fn rank(grammar: &InternalGrammar, product: &CompactNode) -> i32 {
+    match product.expand() {
+        Product { action, .. } => grammar.rule_rank(action),
+        _ => unreachable!(),
+    }
+}
+
+// Moves the alternatives that satisfy the predicate to the front, keeping their order.
+// Returns them.
+pub(super) fn retain<F>(alternatives: &[CompactNode], mut f: F) -> &[CompactNode]
+where
+    F: FnMut(&CompactNode) -> bool,
+{
+    let kept: Vec<_> = alternatives
+        .iter()
+        .filter(|alt| f(alt))
+        .map(|alt| alt.expand())
+        .collect();
+    for (alt, node) in alternatives.iter().zip(&kept) {
+        alt.set(*node);
+    }
+    &alternatives[..kept.len()]
+}
@@ -1,64 +1,88 @@
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::slice;
 
 use bit_vec;
 use cfg::symbol::Symbol;
-use ref_slice::ref_slice;
 
 use forest::bocage::node::Node::*;
 use forest::bocage::node::{CompactNode, Node};
+use forest::bocage::LeafPayload;
 use forest::node_handle::NodeHandle;
+use forest::tree::{PackedTrees, Tree, TreeIter};
 use forest::Bocage;
 use grammar::InternalGrammar;
 
 pub use self::HandleVariant::*;
 
-impl<G> Bocage<G> {
+impl<G, V> Bocage<G, V> {
     // Once node liveness is marked, you may traverse the nodes.
-    pub fn traverse(&self) -> Traverse<G> {
+    pub fn traverse(&self) -> Traverse<'_, G, V> {
         Traverse {
             bocage: self,
             graph_iter: self.graph.iter(),
             liveness_iter: self.gc.liveness.iter(),
             factor_stack: vec![],
+            factor_values: vec![],
             factor_traversal: vec![],
+            leo_expansion: None,
         }
     }
 }
 
-pub struct Traverse<'f, G> {
-    bocage: &'f Bocage<G>,
+pub struct Traverse<'f, G, V = u32> {
+    bocage: &'f Bocage<G, V>,
     // main iterators
     graph_iter: slice::Iter<'f, CompactNode>,
     liveness_iter: bit_vec::Iter<'f>,
     // Space for unrolling factors
-    factor_stack: Vec<(Symbol, u32)>,
+    factor_stack: Vec<(Symbol, NodeHandle)>,
+    factor_values: Vec<(Symbol, u32)>,
     // Scratch space for traversal
     factor_traversal: Vec<NodeHandle>,
+    // Products of an expanded Leo chain, which are traversed in place of the Leo node
+    leo_expansion: Option<LeoExpansion<'f>>,
 }
 
-impl<'f, G> Traverse<'f, G>
+struct LeoExpansion<'f> {
+    node: &'f CompactNode,
+    handle: NodeHandle,
+    products: slice::Iter<'f, CompactNode>,
+    last: NodeHandle,
+}
+
+impl<'f, G, V> Traverse<'f, G, V>
 where
     G: Borrow<InternalGrammar>,
+    V: LeafPayload,
 {
-    pub fn next_node<'t>(&'t mut self) -> Option<TraversalHandle<'f, 't, G>> {
+    pub fn next_node<'t>(&'t mut self) -> Option<TraversalHandle<'f, 't, G, V>> {
+        if let Some((node, handle)) = self.next_leo_product() {
+            return Some(self.product_handle(node, handle));
+        }
         while let (Some(node), Some(alive)) = (self.graph_iter.next(), self.liveness_iter.next()) {
             if !alive {
                 continue;
             }
+            let handle = NodeHandle((self.bocage.graph.len() - self.graph_iter.len() - 1) as u32);
             match node.expand() {
                 Product { action, .. } => {
                     if self.bocage.is_transparent(action) {
                         continue;
                     }
-                    return Some(TraversalHandle {
+                    return Some(self.product_handle(node, handle));
+                }
+                LeoExpanded { first, last } => {
+                    let range = first.usize()..last.usize() + 1;
+                    self.leo_expansion = Some(LeoExpansion {
                         node,
-                        symbol: self.bocage.grammar.borrow().get_lhs(action),
-                        item: SumHandle(Products {
-                            products: ref_slice(node).iter(),
-                            traverse: self,
-                        }),
+                        handle,
+                        products: self.bocage.graph[range].iter(),
+                        last,
                     });
+                    if let Some((node, handle)) = self.next_leo_product() {
+                        return Some(self.product_handle(node, handle));
+                    }
                 }
                 Sum {
                     nonterminal: symbol,
@@ -71,6 +95,7 @@ where
                     }
                     return Some(TraversalHandle {
                         node,
+                        handle,
                         symbol,
                         item: SumHandle(Products {
                             products,
@@ -81,6 +106,7 @@ where
                 NullingLeaf { symbol } => {
                     return Some(TraversalHandle {
                         node,
+                        handle,
                         symbol,
                         item: NullingHandle,
                     });
@@ -88,19 +114,126 @@ where
                 Evaluated { symbol, values } => {
                     return Some(TraversalHandle {
                         node,
+                        handle,
                         symbol,
-                        item: LeafHandle(values),
+                        item: LeafHandle(V::load(values, &self.bocage.leaves)),
+                    });
+                }
+                Error { symbol, skipped } => {
+                    return Some(TraversalHandle {
+                        node,
+                        handle,
+                        symbol,
+                        item: ErrorHandle(skipped),
+                    });
+                }
+                Virtual { symbol, values } => {
+                    return Some(TraversalHandle {
+                        node,
+                        handle,
+                        symbol,
+                        item: VirtualHandle(V::load(values, &self.bocage.leaves)),
+                    });
+                }
+                LeoLink { .. } | Leo { .. } => unreachable!(),
+            }
+        }
+        None
+    }
+
+    /// Traverses the remaining nodes and returns an iterator over the trees rooted
+    /// at `root`.
+    pub fn trees(&mut self, root: NodeHandle) -> TreeIter<V> {
+        let mut packed = PackedTrees::new();
+        let mut finished = BTreeMap::new();
+        while let Some(mut item) = self.next_node() {
+            match item.item {
+                SumHandle(ref mut products) => {
+                    while let Some(product) = products.next_product() {
+                        let children = product
+                            .factor_handles
+                            .iter()
+                            .map(|&(_sym, handle)| finished.get(&handle).cloned());
+                        packed.push_rule(product.action, children);
+                    }
+                }
+                NullingHandle => {
+                    packed.push_leaf(Tree::Nulling {
+                        symbol: item.symbol,
+                    });
+                }
+                LeafHandle(ref value) | VirtualHandle(ref value) => {
+                    packed.push_leaf(Tree::Leaf {
+                        symbol: item.symbol,
+                        value: value.clone(),
+                    });
+                }
+                ErrorHandle(skipped) => {
+                    packed.push_leaf(Tree::Error {
+                        symbol: item.symbol,
+                        skipped,
                     });
                 }
             }
+            finished.insert(item.handle(), packed.end_node());
+            item.end_evaluation();
+        }
+        TreeIter::new(packed, finished.get(&root).cloned())
+    }
+
+    fn product_handle<'t>(
+        &'t mut self,
+        node: &'f CompactNode,
+        handle: NodeHandle,
+    ) -> TraversalHandle<'f, 't, G, V> {
+        let action = match node.expand() {
+            Product { action, .. } => action,
+            _ => unreachable!(),
+        };
+        TraversalHandle {
+            node,
+            handle,
+            symbol: self.bocage.grammar.borrow().get_lhs(action),
+            item: SumHandle(Products {
+                products: slice::from_ref(node).iter(),
+                traverse: self,
+            }),
         }
+    }
+
+    // Returns the next product of the Leo chain that is being traversed. The topmost product
+    // is given the handle of the Leo node. Once the chain is done, its Leo node takes
+    // the place of the topmost product.
+    fn next_leo_product(&mut self) -> Option<(&'f CompactNode, NodeHandle)> {
+        if let Some(ref mut expansion) = self.leo_expansion {
+            while let Some(node) = expansion.products.next() {
+                match node.expand() {
+                    Product { action, .. } => {
+                        if !self.bocage.is_transparent(action) {
+                            let offset = expansion.products.len() as u32;
+                            let handle = if offset == 0 {
+                                expansion.handle
+                            } else {
+                                NodeHandle(expansion.last.0 - offset)
+                            };
+                            return Some((node, handle));
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            let last = &self.bocage.graph[expansion.last.usize()];
+            expansion.node.set(last.expand());
+        }
+        self.leo_expansion = None;
         None
     }
 
     fn unfold_factors(&mut self, left: NodeHandle, right: Option<NodeHandle>) {
         self.factor_stack.clear();
+        self.factor_values.clear();
         self.enqueue_for_unfold(left, right);
-        while let Some(node) = self.pop_for_unfold() {
+        while let Some((node, handle)) = self.pop_for_unfold() {
             match node {
                 Product {
                     left_factor,
@@ -110,7 +243,8 @@ where
                     self.enqueue_for_unfold(left_factor, right_factor);
                 }
                 Evaluated { symbol, values } => {
-                    self.factor_stack.push((symbol, values));
+                    self.factor_stack.push((symbol, handle));
+                    self.factor_values.push((symbol, values));
                 }
                 _ => unreachable!(),
             }
@@ -124,39 +258,46 @@ where
         self.factor_traversal.push(left);
     }
 
-    fn pop_for_unfold(&mut self) -> Option<Node> {
-        self.factor_traversal.pop().map(|handle| {
-            let node = self.bocage.graph[handle.usize()].clone();
-            node.expand()
-        })
+    fn pop_for_unfold(&mut self) -> Option<(Node, NodeHandle)> {
+        self.factor_traversal
+            .pop()
+            .map(|handle| (self.bocage.graph[handle.usize()].expand(), handle))
     }
 }
 
-pub struct TraversalHandle<'f, 't, G> {
+pub struct TraversalHandle<'f, 't, G, V = u32> {
     pub node: &'f CompactNode,
+    handle: NodeHandle,
     pub symbol: Symbol,
-    pub item: HandleVariant<'f, 't, G>,
+    pub item: HandleVariant<'f, 't, G, V>,
 }
 
-pub enum HandleVariant<'f, 't, G> {
-    SumHandle(Products<'f, 't, G>),
+pub enum HandleVariant<'f, 't, G, V = u32> {
+    SumHandle(Products<'f, 't, G, V>),
     NullingHandle,
-    LeafHandle(u32),
+    LeafHandle(V),
+    ErrorHandle(u32),
+    VirtualHandle(V),
 }
 
-pub struct Products<'f, 't, G> {
+pub struct Products<'f, 't, G, V = u32> {
     products: slice::Iter<'f, CompactNode>,
-    traverse: &'t mut Traverse<'f, G>,
+    traverse: &'t mut Traverse<'f, G, V>,
 }
 
 pub struct ProductHandle<'t> {
     pub action: u32,
+    /// Factors with the results given to `set_evaluation_result`. Tokens have their stored
+    /// values.
     pub factors: &'t [(Symbol, u32)],
+    /// Factors with their handles.
+    pub factor_handles: &'t [(Symbol, NodeHandle)],
 }
 
-impl<'f, 't, G> Products<'f, 't, G>
+impl<'f, 't, G, V> Products<'f, 't, G, V>
 where
     G: Borrow<InternalGrammar>,
+    V: LeafPayload,
 {
     pub fn next_product<'p>(&'p mut self) -> Option<ProductHandle> {
         while let Some(node) = self.products.next() {
@@ -176,7 +317,8 @@ where
                         self.traverse.unfold_factors(left_factor, right_factor);
                         return Some(ProductHandle {
                             action,
-                            factors: &self.traverse.factor_stack[..],
+                            factors: &self.traverse.factor_values[..],
+                            factor_handles: &self.traverse.factor_stack[..],
                         });
                     }
                 }
@@ -187,11 +329,30 @@ where
     }
 }
 
-impl<'f, 't, G> TraversalHandle<'f, 't, G> {
+impl<'f, 't, G, V> TraversalHandle<'f, 't, G, V> {
+    /// Marks the node as evaluated. Products that have it as a factor refer to it
+    /// by its handle.
+    pub fn end_evaluation(&self) {
+        if let Evaluated { .. } = self.node.expand() {
+            // Keep the token's value.
+            return;
+        }
+        self.node.set(Evaluated {
+            symbol: self.symbol,
+            values: 0,
+        });
+    }
+
+    /// Marks the node as evaluated and stores the result, which products that have it as
+    /// a factor find in `ProductHandle::factors`.
     pub fn set_evaluation_result(&self, values: u32) {
         self.node.set(Evaluated {
             symbol: self.symbol,
             values,
         });
     }
+
+    pub fn handle(&self) -> NodeHandle {
+        self.handle
+    }
 }
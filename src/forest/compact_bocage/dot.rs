@@ -0,0 +1,199 @@
+use std::borrow::Borrow;
+use std::io::{self, Write};
+
+use bit_vec::BitVec;
+
+use forest::compact_bocage::node::Node::{self, *};
+use forest::compact_bocage::CompactBocage;
+use forest::dot::{DotId, DotWriter};
+use forest::node_handle::NodeHandle;
+use grammar::InternalGrammar;
+
+impl<G> CompactBocage<G>
+where
+    G: Borrow<InternalGrammar>,
+{
+    /// Writes the forest rooted at `root` in the Graphviz dot language. Products of
+    /// internal rules are hidden, and their factors are shown in their place.
+    pub fn write_dot<W: Write>(&self, root: NodeHandle, out: &mut W) -> io::Result<()> {
+        self.write_dot_with(root, DotWriter::new(out, self.grammar.borrow(), false)?)
+    }
+
+    /// Writes the forest rooted at `root` in the Graphviz dot language, including
+    /// products of internal rules.
+    pub fn write_dot_with_transparent<W: Write>(
+        &self,
+        root: NodeHandle,
+        out: &mut W,
+    ) -> io::Result<()> {
+        self.write_dot_with(root, DotWriter::new(out, self.grammar.borrow(), true)?)
+    }
+
+    fn write_dot_with<W: Write>(&self, root: NodeHandle, mut dot: DotWriter<W>) -> io::Result<()> {
+        let mut visited = BitVec::from_elem(self.graph.vec.len(), false);
+        let mut stack = vec![root];
+        while let Some(handle) = stack.pop() {
+            if visited[handle.usize()] {
+                continue;
+            }
+            visited.set(handle.usize(), true);
+            let id = DotId::node(handle);
+            match self.graph.get(handle) {
+                Sum { nonterminal, .. } => {
+                    dot.sum(id, nonterminal)?;
+                    for (i, summand) in
+                        CompactBocage::<G>::summands(&self.graph, handle).enumerate()
+                    {
+                        if self.is_hidden(summand, &dot) {
+                            continue;
+                        }
+                        let summand_id = DotId::part(handle, i as u32);
+                        dot.edge(id, summand_id)?;
+                        self.write_dot_product(summand_id, summand, &mut dot, &mut stack)?;
+                    }
+                }
+                product @ Product { .. } => {
+                    self.write_dot_product(id, product, &mut dot, &mut stack)?;
+                }
+                LeoExpanded { last, .. } => {
+                    let product = self.graph.get(last);
+                    self.write_dot_product(id, product, &mut dot, &mut stack)?;
+                }
+                Leo { chain, bottom } => {
+                    self.write_dot_leo(handle, chain, bottom, &mut dot, &mut stack)?;
+                }
+                NullingLeaf { symbol } => {
+                    dot.nulling(id, symbol)?;
+                }
+                Evaluated { symbol, values } => {
+                    dot.leaf(id, symbol, &values)?;
+                }
+                Error { symbol, skipped } => {
+                    dot.error(id, symbol, skipped)?;
+                }
+                Virtual { symbol, values } => {
+                    dot.virtual_leaf(id, symbol, &values)?;
+                }
+            }
+        }
+        dot.finish()
+    }
+
+    // Writes the products of a Leo chain as if it was expanded. The topmost product
+    // stands for the Leo node.
+    fn write_dot_leo<W: Write>(
+        &self,
+        handle: NodeHandle,
+        chain: NodeHandle,
+        bottom: NodeHandle,
+        dot: &mut DotWriter<W>,
+        stack: &mut Vec<NodeHandle>,
+    ) -> io::Result<()> {
+        let mut links = vec![];
+        let mut link = Some(chain);
+        while let Some(link_handle) = link {
+            if let Product {
+                action,
+                left_factor,
+                right_factor: up,
+            } = self.graph.get(link_handle)
+            {
+                links.push((action, left_factor));
+                link = up;
+            } else {
+                unreachable!()
+            }
+        }
+        let mut right = vec![];
+        self.push_dot_factor(bottom, dot, &mut right);
+        stack.extend(right.iter().cloned());
+        let mut right: Vec<_> = right.into_iter().map(DotId::node).collect();
+        for (i, &(action, left_factor)) in links.iter().enumerate() {
+            let mut factors = vec![];
+            self.push_dot_factor(left_factor, dot, &mut factors);
+            stack.extend(factors.iter().cloned());
+            let mut factors: Vec<_> = factors.into_iter().map(DotId::node).collect();
+            factors.append(&mut right);
+            let is_top = i + 1 == links.len();
+            if !is_top && !dot.show_transparent && self.is_transparent(action) {
+                right = factors;
+                continue;
+            }
+            let id = if is_top {
+                DotId::node(handle)
+            } else {
+                DotId::part(handle, i as u32)
+            };
+            dot.product(id, self.grammar.borrow().external_origin(action))?;
+            for factor in factors {
+                dot.edge(id, factor)?;
+            }
+            right.push(id);
+        }
+        Ok(())
+    }
+
+    fn write_dot_product<W: Write>(
+        &self,
+        id: DotId,
+        product: Node,
+        dot: &mut DotWriter<W>,
+        stack: &mut Vec<NodeHandle>,
+    ) -> io::Result<()> {
+        if let Product {
+            action,
+            left_factor,
+            right_factor,
+        } = self.process_product_tree_node(product)
+        {
+            let origin = if self.is_transparent(action) {
+                None
+            } else {
+                self.grammar.borrow().external_origin(action)
+            };
+            dot.product(id, origin)?;
+            let mut factors = vec![];
+            self.push_dot_factor(left_factor, dot, &mut factors);
+            if let Some(right_factor) = right_factor {
+                self.push_dot_factor(right_factor, dot, &mut factors);
+            }
+            for factor in factors {
+                dot.edge(id, DotId::node(factor))?;
+                stack.push(factor);
+            }
+        }
+        Ok(())
+    }
+
+    // Pushes a factor, or factors of a hidden product in its place.
+    fn push_dot_factor<W>(
+        &self,
+        factor: NodeHandle,
+        dot: &DotWriter<W>,
+        factors: &mut Vec<NodeHandle>,
+    ) {
+        let node = self.graph.get(factor);
+        if self.is_hidden(node, dot) {
+            if let Product {
+                left_factor,
+                right_factor,
+                ..
+            } = self.process_product_tree_node(node)
+            {
+                self.push_dot_factor(left_factor, dot, factors);
+                if let Some(right_factor) = right_factor {
+                    self.push_dot_factor(right_factor, dot, factors);
+                }
+            }
+        } else {
+            factors.push(factor);
+        }
+    }
+
+    fn is_hidden<W>(&self, node: Node, dot: &DotWriter<W>) -> bool {
+        match node {
+            Product { action, .. } => !dot.show_transparent && self.is_transparent(action),
+            _ => false,
+        }
+    }
+}
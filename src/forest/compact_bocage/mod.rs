@@ -1,21 +1,24 @@
+mod dot;
 pub mod node;
 pub mod order;
 pub mod traverse;
 
 use std::borrow::Borrow;
 use std::hint;
+use std::iter;
 
 use bit_vec::BitVec;
 use cfg::symbol::Symbol;
 
 use forest::node_handle::NodeHandle;
+use forest::tree::{self, TreeCount};
 use forest::Forest;
-use grammar::InternalGrammar;
+use grammar::{InternalGrammar, MAX_NULLING_SYMBOLS};
 use item::CompletedItem;
 
 use self::node::Node::*;
 use self::node::{Graph, Node, NULL_ACTION};
-use self::order::Order;
+use self::order::{filter_products, Order};
 
 pub struct CompactBocage<G> {
     pub(crate) graph: Graph,
@@ -59,7 +62,7 @@ where
         // self.nulling_leaf_count = self.nulling_symbol_count();
         let nulling_leaf_count = self.nulling_symbol_count();
         // Ensure that `max` is not ridiculously large.
-        assert!(nulling_leaf_count < (1 << 20), "invalid nullable symbol");
+        assert!(nulling_leaf_count < MAX_NULLING_SYMBOLS, "invalid nullable symbol");
         let mut graph: Vec<Node> = (0..nulling_leaf_count)
             .map(|i| NullingLeaf {
                 symbol: Symbol::from(i),
@@ -106,20 +109,193 @@ where
             .map_or(1, |m| m + 1)
     }
 
+    /// Marks nodes reachable from `root`, letting the order remove alternatives. A node that
+    /// is left without alternatives is removed along with products that have it as a factor.
+    /// When the order removes every tree, `root` is left unmarked.
     #[inline]
-    pub fn mark_alive<O: Order>(&mut self, root: NodeHandle, _order: O) {
-        self.gc.liveness.clear();
+    pub fn mark_alive<O: Order>(&mut self, root: NodeHandle, mut order: O) {
+        // Clearing a bit vector keeps its length.
+        self.gc.liveness.truncate(0);
         self.gc.liveness.grow(self.graph.vec.len(), false);
         self.gc.dfs.push(root);
+        let mut removed = vec![];
         while let Some(node) = self.gc.dfs.pop() {
             self.gc.liveness.set(node.usize(), true);
+            if let Leo { chain, bottom } = self.graph.get(node) {
+                self.expand_leo(node, chain, bottom);
+            }
+            if let LeoExpanded { first, last } = self.graph.get(node) {
+                self.gc.dfs_queue_leo_factors(&self.graph, first, last);
+                continue;
+            }
+            if !self.apply_order(node, &mut order) {
+                removed.push(node);
+                continue;
+            }
             let summands = CompactBocage::<G>::summands(&self.graph, node);
-            // let summands = order.sum(summands);
             for summand in summands {
-                // TODO: use order for products.
                 self.gc.dfs_queue_factors(summand);
             }
         }
+        if !removed.is_empty() {
+            self.remove_with_factors(removed);
+        }
+    }
+
+    /// Unmarks removed nodes and nodes that are left without alternatives once products
+    /// that refer to them are removed. Factors precede the nodes that refer to them, so
+    /// a single pass over marked nodes is enough.
+    fn remove_with_factors(&mut self, removed: Vec<NodeHandle>) {
+        let mut dead = BitVec::from_elem(self.graph.vec.len(), false);
+        for node in removed {
+            dead.set(node.usize(), true);
+        }
+        for i in 0..self.graph.vec.len() {
+            if dead[i] || !self.gc.liveness[i] {
+                continue;
+            }
+            let node = NodeHandle(i as u32);
+            let has_dead_factor = |alternative: &Node| match *alternative {
+                Product {
+                    left_factor,
+                    right_factor,
+                    ..
+                } => iter::once(left_factor)
+                    .chain(right_factor)
+                    .any(|factor| dead[factor.usize()]),
+                _ => false,
+            };
+            let alive = match self.graph.get(node) {
+                Sum { .. } => Self::retain_summands(&mut self.graph, node, |alternatives| {
+                    alternatives.retain(|alternative| !has_dead_factor(alternative))
+                }),
+                LeoExpanded { first, last } => {
+                    let mut products = self.graph.iter_from(first);
+                    let mut alive = true;
+                    while products.handle <= last {
+                        if let Some(Product {
+                            left_factor,
+                            right_factor,
+                            ..
+                        }) = products.next()
+                        {
+                            let mut factors = iter::once(left_factor).chain(right_factor);
+                            alive &= !factors.any(|factor| factor < first && dead[factor.usize()]);
+                        }
+                    }
+                    alive
+                }
+                other => !has_dead_factor(&other),
+            };
+            if !alive {
+                dead.set(i, true);
+                self.gc.liveness.set(i, false);
+            }
+        }
+    }
+
+    /// Returns the number of trees rooted at `root`. Call it before the traversal, which
+    /// overwrites nodes.
+    pub fn count_trees(&self, root: NodeHandle) -> TreeCount {
+        tree::count_trees(root, self.graph.vec.len(), |node, alternatives| {
+            for summand in CompactBocage::<G>::summands(&self.graph, node) {
+                alternatives.push(match summand {
+                    Product {
+                        left_factor,
+                        right_factor,
+                        ..
+                    } => [Some(left_factor), right_factor],
+                    Leo { chain, bottom } => [Some(chain), Some(bottom)],
+                    LeoExpanded { last, .. } => [Some(last), None],
+                    NullingLeaf { .. } | Evaluated { .. } | Error { .. } | Virtual { .. } => {
+                        [None, None]
+                    }
+                    Sum { .. } => unreachable!(),
+                });
+            }
+        })
+    }
+
+    /// Expands a Leo chain into products that are placed at the end of the graph. The Leo
+    /// node keeps the range of these products. The traversal evaluates them in place of
+    /// the Leo node.
+    fn expand_leo(&mut self, node: NodeHandle, chain: NodeHandle, bottom: NodeHandle) {
+        let first = NodeHandle(self.graph.vec.len() as u32);
+        let mut right_factor = bottom;
+        let mut link = Some(chain);
+        while let Some(handle) = link {
+            if let Product {
+                action,
+                left_factor,
+                right_factor: up,
+            } = self.graph.get(handle)
+            {
+                right_factor = self.graph.push(Product {
+                    action,
+                    left_factor,
+                    right_factor: Some(right_factor),
+                });
+                link = up;
+            } else {
+                unreachable!()
+            }
+        }
+        let last = right_factor;
+        self.graph.set_at(node, LeoExpanded { first, last });
+        let grow = self.graph.vec.len() - self.gc.liveness.len();
+        self.gc.liveness.grow(grow, false);
+    }
+
+    /// Lets the order remove alternatives of a sum node and its products. Returns `false`
+    /// when all alternatives were removed.
+    fn apply_order<O: Order>(&mut self, node: NodeHandle, order: &mut O) -> bool {
+        let grammar = self.grammar.borrow();
+        Self::retain_summands(&mut self.graph, node, |alternatives| {
+            order.sum(alternatives);
+            filter_products(order, grammar, alternatives);
+        })
+    }
+
+    /// Lets `f` remove alternatives of a sum node. Remaining products are written anew at
+    /// lower positions, which keeps them from growing. Returns `false` when all alternatives
+    /// were removed, in which case the node is left as it was.
+    fn retain_summands<F>(graph: &mut Graph, node: NodeHandle, f: F) -> bool
+    where
+        F: FnOnce(&mut Vec<Node>),
+    {
+        let mut iter = graph.iter_from(node);
+        let nonterminal = match iter.next() {
+            Some(Sum { nonterminal, .. }) => nonterminal,
+            _ => return true,
+        };
+        let mut alternatives: Vec<_> = CompactBocage::<G>::summands(graph, node).collect();
+        let count = alternatives.len();
+        // Find the end of the sum node's products.
+        for _ in 0..count {
+            iter.next();
+        }
+        let end = iter.handle;
+        f(&mut alternatives);
+        if alternatives.len() == count {
+            return true;
+        }
+        if alternatives.is_empty() {
+            return false;
+        }
+        let mut nodes = vec![];
+        if alternatives.len() > 1 {
+            nodes.push(Sum {
+                nonterminal,
+                count: alternatives.len() as u32,
+            });
+        }
+        nodes.extend(alternatives);
+        let mut handle = node;
+        for node in nodes {
+            handle = graph.set_at(handle, node);
+        }
+        graph.fill_nop(handle, end);
+        true
     }
 
     #[inline]
@@ -213,8 +389,29 @@ impl MarkAndSweep {
                     self.dfs.push(left_factor);
                 }
             }
-            NullingLeaf { .. } | Evaluated { .. } => {}
-            Sum { .. } => unreachable!(),
+            NullingLeaf { .. } | Evaluated { .. } | Error { .. } | Virtual { .. } => {}
+            Sum { .. } | Leo { .. } | LeoExpanded { .. } => unreachable!(),
+        }
+    }
+
+    /// Queues factors of products expanded from a Leo chain. The products themselves are
+    /// not marked, because they are reached only through their Leo node.
+    fn dfs_queue_leo_factors(&mut self, graph: &Graph, first: NodeHandle, last: NodeHandle) {
+        let mut products = graph.iter_from(first);
+        while products.handle <= last {
+            if let Some(Product {
+                left_factor,
+                right_factor,
+                ..
+            }) = products.next()
+            {
+                let factors = iter::once(left_factor).chain(right_factor);
+                for factor in factors.filter(|&factor| factor < first) {
+                    if let Some(false) = self.liveness.get(factor.usize()) {
+                        self.dfs.push(factor);
+                    }
+                }
+            }
         }
     }
 }
@@ -265,12 +462,62 @@ where
     }
 
     #[inline]
-    fn leaf(&mut self, token: Symbol, _pos: u32, _value: Self::LeafValue) -> Self::NodeRef {
-        self.graph.push(Evaluated { symbol: token })
+    fn leaf(&mut self, token: Symbol, _pos: u32, value: Self::LeafValue) -> Self::NodeRef {
+        self.graph.push(Evaluated {
+            symbol: token,
+            values: value,
+        })
+    }
+
+    #[inline]
+    fn error(&mut self, token: Symbol, _pos: u32, skipped: u32) -> Self::NodeRef {
+        self.graph.push(Error {
+            symbol: token,
+            skipped,
+        })
+    }
+
+    #[inline]
+    fn virtual_leaf(&mut self, token: Symbol, _pos: u32, value: Self::LeafValue) -> Self::NodeRef {
+        self.graph.push(Virtual {
+            symbol: token,
+            values: value,
+        })
     }
 
     #[inline]
     fn nulling(&self, token: Symbol) -> Self::NodeRef {
         NodeHandle::nulling(token)
     }
+
+    #[inline]
+    fn leo_link(
+        &mut self,
+        dot: u32,
+        left: Self::NodeRef,
+        up: Option<Self::NodeRef>,
+    ) -> Self::NodeRef {
+        // The link is stored as a product whose right factor is the next link.
+        self.graph.push(Product {
+            action: dot,
+            left_factor: left,
+            right_factor: up,
+        })
+    }
+
+    /// The chain is expanded once the node is marked alive.
+    #[inline]
+    fn leo(&mut self, chain: Self::NodeRef, bottom: Self::NodeRef) -> Self::NodeRef {
+        self.graph.push(Leo { chain, bottom })
+    }
+
+    #[inline]
+    fn graph_len(&self) -> usize {
+        self.graph.vec.len()
+    }
+
+    #[inline]
+    fn truncate_graph(&mut self, len: usize) {
+        self.graph.vec.truncate(len);
+    }
 }
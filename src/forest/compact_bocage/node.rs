@@ -48,6 +48,25 @@ impl Graph {
         }
     }
 
+    /// Writes a node at the given position, which must have enough room for it. Returns
+    /// the position that follows the node.
+    pub(crate) fn set_at(&mut self, handle: NodeHandle, node: Node) -> NodeHandle {
+        let (node_repr, size) = node.to_repr(handle.0);
+        for i in 0..size {
+            unsafe {
+                self.vec[handle.usize() + i].set(node_repr.fields[i]);
+            }
+        }
+        NodeHandle(handle.0 + size as u32)
+    }
+
+    /// Fills positions from `start` up to `end` with padding.
+    pub(crate) fn fill_nop(&mut self, start: NodeHandle, end: NodeHandle) {
+        for cell in &self.vec[start.usize()..end.usize()] {
+            cell.set(NopTag.to_u16());
+        }
+    }
+
     pub(crate) fn get(&self, handle: NodeHandle) -> Node {
         self.iter_from(handle).next().unwrap()
     }
@@ -100,6 +119,17 @@ impl<'a> Iter<'a> {
     pub(crate) fn peek(&mut self) -> Option<Node> {
         self.clone().next()
     }
+
+    /// Moves past padding, so that the handle points to the next node.
+    #[inline]
+    pub(crate) fn skip_nop(&mut self) {
+        while let Some(cell) = self.vec.get(self.handle.usize()) {
+            if cell.get() != NopTag.to_u16() {
+                break;
+            }
+            self.handle.0 += 1;
+        }
+    }
 }
 
 // Node variants `Sum`/`Product` are better known in literature as `OR`/`AND`.
@@ -123,8 +153,31 @@ pub enum Node {
         symbol: Symbol,
     },
     Evaluated {
-        /// 4 bytes.
+        /// 2 to 8 bytes.
+        symbol: Symbol,
+        values: u32,
+    },
+    Error {
+        /// 10 bytes.
+        symbol: Symbol,
+        skipped: u32,
+    },
+    Virtual {
+        /// 10 bytes.
         symbol: Symbol,
+        values: u32,
+    },
+    Leo {
+        /// 10 bytes.
+        /// Invariant: the chain is made of products whose right factor is the next link.
+        chain: NodeHandle,
+        bottom: NodeHandle,
+    },
+    LeoExpanded {
+        /// 10 bytes.
+        /// Products of the expanded chain, from the bottom up.
+        first: NodeHandle,
+        last: NodeHandle,
     },
 }
 
@@ -136,10 +189,12 @@ union NodeRepr {
     medium_link: MediumLinkRepr,
     small_product: SmallProductRepr,
     small_leaf: SmallLeafRepr,
+    medium_leaf: MediumLeafRepr,
     small_nulling_leaf: SmallNullingLeafRepr,
     sum: SumRepr,
     product: ProductRepr,
     leaf: LeafRepr,
+    escaped: EscapedRepr,
     nop: NopRepr,
 }
 
@@ -194,6 +249,7 @@ struct SmallNullingLeafRepr {
 #[derive(Clone, Copy)]
 struct LeafRepr {
     symbol: Symbol,
+    values: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -201,6 +257,21 @@ struct SmallLeafRepr {
     symbol: u16,
 }
 
+#[derive(Clone, Copy)]
+struct MediumLeafRepr {
+    symbol: u16,
+    values: u16,
+}
+
+// A node that is told apart by its whole first field.
+#[derive(Clone, Copy)]
+#[repr(packed)]
+struct EscapedRepr {
+    _tag: u16,
+    first: u32,
+    second: u32,
+}
+
 #[derive(Clone, Copy)]
 struct NopRepr {
     nop: u16,
@@ -212,21 +283,33 @@ pub(super) enum Tag {
     SmallLinkTag = 0b001 << TAG_BIT,
     MediumLinkTag = 0b010 << TAG_BIT,
     SmallProductTag = 0b011 << TAG_BIT,
-    SmallLeafTag = 0b100 << TAG_BIT,
-    // SmallNonnullingLeaf = 0b1000 << (TAG_BIT - 1),
+    SmallLeafTag = 0b10000 << (TAG_BIT - 2),
+    MediumLeafTag = 0b10001 << (TAG_BIT - 2),
     SmallNullingLeafTag = 0b1001 << (TAG_BIT - 1),
     LeafTag = 0b101 << TAG_BIT,
     SumTag = 0b111 << TAG_BIT,
     ProductTag = 0b110 << TAG_BIT,
+    LeoExpandedTag = 0b1111_1111_1111_1011,
+    LeoTag = 0b1111_1111_1111_1100,
+    VirtualTag = 0b1111_1111_1111_1101,
+    ErrorTag = 0b1111_1111_1111_1110,
     NopTag = 0b1111_1111_1111_1111,
 }
 
 impl Tag {
     #[inline]
-    fn from_u16(num: u16) -> Option<Self> {
+    pub(super) fn from_u16(num: u16) -> Option<Self> {
         let n = num & TAG_MASK;
         if num == NopTag.to_u16() {
             Some(NopTag)
+        } else if num == ErrorTag.to_u16() {
+            Some(ErrorTag)
+        } else if num == VirtualTag.to_u16() {
+            Some(VirtualTag)
+        } else if num == LeoTag.to_u16() {
+            Some(LeoTag)
+        } else if num == LeoExpandedTag.to_u16() {
+            Some(LeoExpandedTag)
         } else if n == LeafTag.to_u16() {
             Some(LeafTag)
         } else if n == SumTag.to_u16() {
@@ -241,12 +324,13 @@ impl Tag {
             Some(MediumLinkTag)
         } else if n == SmallProductTag.to_u16() {
             Some(SmallProductTag)
-        } else if n == SmallLeafTag.to_u16() {
-            let n = num & SMALL_LEAF_TAG_MASK;
-            if n == SmallLeafTag.to_u16() {
-                Some(SmallLeafTag)
-            } else if n == SmallNullingLeafTag.to_u16() {
+        } else if n == SmallLeafTag.to_u16() & TAG_MASK {
+            if num & SMALL_LEAF_TAG_MASK == SmallNullingLeafTag.to_u16() {
                 Some(SmallNullingLeafTag)
+            } else if num & VALUE_LEAF_TAG_MASK == SmallLeafTag.to_u16() {
+                Some(SmallLeafTag)
+            } else if num & VALUE_LEAF_TAG_MASK == MediumLeafTag.to_u16() {
+                Some(MediumLeafTag)
             } else {
                 None
             }
@@ -262,12 +346,16 @@ impl Tag {
             SmallLinkTag => 0b001 << TAG_BIT,
             MediumLinkTag => 0b010 << TAG_BIT,
             SmallProductTag => 0b011 << TAG_BIT,
-            SmallLeafTag => 0b100 << TAG_BIT,
-            // SmallNonnullingLeaf = 0b1000 << (TAG_BIT - 1),
+            SmallLeafTag => 0b10000 << (TAG_BIT - 2),
+            MediumLeafTag => 0b10001 << (TAG_BIT - 2),
             SmallNullingLeafTag => 0b1001 << (TAG_BIT - 1),
             LeafTag => 0b101 << TAG_BIT,
             SumTag => 0b111 << TAG_BIT,
             ProductTag => 0b110 << TAG_BIT,
+            LeoExpandedTag => 0b1111_1111_1111_1011,
+            LeoTag => 0b1111_1111_1111_1100,
+            VirtualTag => 0b1111_1111_1111_1101,
+            ErrorTag => 0b1111_1111_1111_1110,
             NopTag => 0b1111_1111_1111_1111,
         }
     }
@@ -279,12 +367,16 @@ impl Tag {
             SmallLinkTag => TAG_MASK,
             MediumLinkTag => TAG_MASK,
             SmallProductTag => TAG_MASK,
-            SmallLeafTag => SMALL_LEAF_TAG_MASK,
-            // SmallNonnullingLeaf = 0b1000 << (TAG_BIT - 1),
+            SmallLeafTag => VALUE_LEAF_TAG_MASK,
+            MediumLeafTag => VALUE_LEAF_TAG_MASK,
             SmallNullingLeafTag => SMALL_LEAF_TAG_MASK,
             LeafTag => TAG_MASK,
             SumTag => TAG_MASK,
             ProductTag => TAG_MASK,
+            LeoExpandedTag => 0b1111_1111_1111_1111,
+            LeoTag => 0b1111_1111_1111_1111,
+            VirtualTag => 0b1111_1111_1111_1111,
+            ErrorTag => 0b1111_1111_1111_1111,
             NopTag => 0b1111_1111_1111_1111,
         }
     }
@@ -297,10 +389,15 @@ impl Tag {
             MediumLinkTag => 2,
             SmallProductTag => 2,
             SmallLeafTag => 1,
+            MediumLeafTag => 2,
             SmallNullingLeafTag => 1,
             LeafTag => 4,
             SumTag => 4,
             ProductTag => 6,
+            LeoExpandedTag => 5,
+            LeoTag => 5,
+            VirtualTag => 5,
+            ErrorTag => 5,
             NopTag => 1,
         }
     }
@@ -309,6 +406,8 @@ impl Tag {
 const TAG_BIT: usize = 5 + 8;
 const TAG_MASK: u16 = 0b111 << TAG_BIT;
 const SMALL_LEAF_TAG_MASK: u16 = 0b1111 << (TAG_BIT - 1);
+const VALUE_LEAF_TAG_MASK: u16 = 0b1_1111 << (TAG_BIT - 2);
+const NULL_VALUES: u32 = 0xFFFF_FFFF;
 pub(super) const NULL_ACTION: u32 = !((TAG_MASK as u32) << 16);
 
 impl NodeRepr {
@@ -396,13 +495,65 @@ impl NodeRepr {
                     SmallLeafTag,
                 ) => Evaluated {
                     symbol: Symbol::from(symbol as u32),
+                    values: 0,
                 },
                 (
                     NodeRepr {
-                        leaf: LeafRepr { symbol },
+                        medium_leaf: MediumLeafRepr { symbol, values },
+                    },
+                    MediumLeafTag,
+                ) => Evaluated {
+                    symbol: Symbol::from(symbol as u32),
+                    values: values as u32,
+                },
+                (
+                    NodeRepr {
+                        leaf: LeafRepr { symbol, values },
                     },
                     LeafTag,
-                ) => Evaluated { symbol },
+                ) => {
+                    if values == NULL_VALUES {
+                        NullingLeaf { symbol }
+                    } else {
+                        Evaluated { symbol, values }
+                    }
+                }
+                (
+                    NodeRepr {
+                        escaped: EscapedRepr { first, second, .. },
+                    },
+                    ErrorTag,
+                ) => Error {
+                    symbol: Symbol::from(first),
+                    skipped: second,
+                },
+                (
+                    NodeRepr {
+                        escaped: EscapedRepr { first, second, .. },
+                    },
+                    VirtualTag,
+                ) => Virtual {
+                    symbol: Symbol::from(first),
+                    values: second,
+                },
+                (
+                    NodeRepr {
+                        escaped: EscapedRepr { first, second, .. },
+                    },
+                    LeoTag,
+                ) => Leo {
+                    chain: NodeHandle(first),
+                    bottom: NodeHandle(second),
+                },
+                (
+                    NodeRepr {
+                        escaped: EscapedRepr { first, second, .. },
+                    },
+                    LeoExpandedTag,
+                ) => LeoExpanded {
+                    first: NodeHandle(first),
+                    last: NodeHandle(second),
+                },
                 _ => unreachable!(),
             }
         }
@@ -485,15 +636,52 @@ impl Node {
                     },
                 },
                 (NullingLeaf { symbol }, LeafTag) => NodeRepr {
-                    leaf: LeafRepr { symbol },
+                    leaf: LeafRepr {
+                        symbol,
+                        values: NULL_VALUES,
+                    },
                 },
-                (Evaluated { symbol }, SmallLeafTag) => NodeRepr {
+                (Evaluated { symbol, .. }, SmallLeafTag) => NodeRepr {
                     small_leaf: SmallLeafRepr {
                         symbol: symbol.usize() as u16,
                     },
                 },
-                (Evaluated { symbol }, LeafTag) => NodeRepr {
-                    leaf: LeafRepr { symbol },
+                (Evaluated { symbol, values }, MediumLeafTag) => NodeRepr {
+                    medium_leaf: MediumLeafRepr {
+                        symbol: symbol.usize() as u16,
+                        values: values as u16,
+                    },
+                },
+                (Evaluated { symbol, values }, LeafTag) => NodeRepr {
+                    leaf: LeafRepr { symbol, values },
+                },
+                (Error { symbol, skipped }, ErrorTag) => NodeRepr {
+                    escaped: EscapedRepr {
+                        _tag: 0,
+                        first: symbol.usize() as u32,
+                        second: skipped,
+                    },
+                },
+                (Virtual { symbol, values }, VirtualTag) => NodeRepr {
+                    escaped: EscapedRepr {
+                        _tag: 0,
+                        first: symbol.usize() as u32,
+                        second: values,
+                    },
+                },
+                (Leo { chain, bottom }, LeoTag) => NodeRepr {
+                    escaped: EscapedRepr {
+                        _tag: 0,
+                        first: chain.0,
+                        second: bottom.0,
+                    },
+                },
+                (LeoExpanded { first, last }, LeoExpandedTag) => NodeRepr {
+                    escaped: EscapedRepr {
+                        _tag: 0,
+                        first: first.0,
+                        second: last.0,
+                    },
                 },
                 _ => unreachable!(),
             };
@@ -545,13 +733,19 @@ impl Node {
                     LeafTag
                 }
             }
-            Evaluated { symbol } => {
-                if symbol.usize() < (1 << (4 + 8)) {
+            Evaluated { symbol, values } => {
+                if symbol.usize() < (1 << (3 + 8)) && values == 0 {
                     SmallLeafTag
+                } else if symbol.usize() < (1 << (3 + 8)) && values < (1 << 16) {
+                    MediumLeafTag
                 } else {
                     LeafTag
                 }
             }
+            Error { .. } => ErrorTag,
+            Virtual { .. } => VirtualTag,
+            Leo { .. } => LeoTag,
+            LeoExpanded { .. } => LeoExpandedTag,
             Sum { nonterminal, count } => {
                 if count < (1 << 5) && nonterminal.usize() < (1 << 8) {
                     SmallSumTag
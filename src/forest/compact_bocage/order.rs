@@ -1,14 +1,19 @@
-use std::cell::Cell;
-
 use cfg::symbol::Symbol;
 
+use forest::bocage::order::RankOrder;
+use grammar::InternalGrammar;
+
+use super::node::Node;
+use super::node::Node::*;
+
 pub trait Order {
-    /// Apply the order to sum node alternatives.
-    fn sum<'b>(&mut self, alternatives: &'b [Cell<u16>]) -> &'b [Cell<u16>] {
-        alternatives
-    }
+    /// Apply the order to sum node alternatives. Alternatives may be removed, but not
+    /// reordered, because products can't be moved to later positions in the compact graph.
+    fn sum(&mut self, _alternatives: &mut Vec<Node>) {}
 
-    /// Apply the order to product node factors.
+    /// Apply the order to product node factors, which are given as pairs of an internal
+    /// symbol and a node handle. Called for every alternative of a sum node that is kept
+    /// by `sum`. Returning the index of a factor removes the product from the sum.
     fn product(&mut self, _factors: &[(Symbol, u32)]) -> Option<usize> {
         None
     }
@@ -24,3 +29,40 @@ impl NullOrder {
         NullOrder
     }
 }
+
+impl<'g> Order for RankOrder<'g> {
+    fn sum(&mut self, alternatives: &mut Vec<Node>) {
+        let grammar = self.grammar;
+        let rank = |alt: &Node| match *alt {
+            Product { action, .. } => grammar.rule_rank(action),
+            _ => unreachable!(),
+        };
+        if let Some(max_rank) = alternatives.iter().map(rank).max() {
+            alternatives.retain(|alt| rank(alt) == max_rank);
+        }
+    }
+}
+
+// Lets the order remove sum alternatives through their factors.
+pub(super) fn filter_products<O: Order>(
+    order: &mut O,
+    grammar: &InternalGrammar,
+    alternatives: &mut Vec<Node>,
+) {
+    alternatives.retain(|alt| match *alt {
+        Product {
+            action,
+            left_factor,
+            right_factor,
+        } if action < grammar.num_rules() as u32 => {
+            let symbols = grammar.factor_symbols(action);
+            let factors: Vec<_> = symbols
+                .iter()
+                .zip(&[Some(left_factor), right_factor])
+                .filter_map(|(&sym, &factor)| Some((sym?, factor?.0)))
+                .collect();
+            order.product(&factors).is_none()
+        }
+        _ => true,
+    });
+}
@@ -1,4 +1,5 @@
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::iter;
 
 use bit_vec::BitVec;
@@ -7,6 +8,7 @@ use cfg::symbol::Symbol;
 use forest::compact_bocage::node::Node::*;
 use forest::compact_bocage::node::{Iter, Node, Tag};
 use forest::node_handle::NodeHandle;
+use forest::tree::{PackedTrees, Tree, TreeIter};
 use forest::CompactBocage;
 use grammar::InternalGrammar;
 
@@ -21,6 +23,7 @@ impl<G> CompactBocage<G> {
             liveness: &self.gc.liveness,
             factor_stack: vec![],
             factor_traversal: vec![],
+            leo_expansion: None,
         }
     }
 }
@@ -34,6 +37,14 @@ pub struct Traverse<'f, G> {
     factor_stack: Vec<(Symbol, NodeHandle)>,
     // Scratch space for traversal
     factor_traversal: Vec<NodeHandle>,
+    // Products of an expanded Leo chain, which are traversed in place of the Leo node
+    leo_expansion: Option<LeoExpansion<'f>>,
+}
+
+struct LeoExpansion<'f> {
+    handle: NodeHandle,
+    products: Iter<'f>,
+    last: NodeHandle,
 }
 
 impl<'f, G> Traverse<'f, G>
@@ -41,7 +52,12 @@ where
     G: Borrow<InternalGrammar>,
 {
     pub fn next_node<'t>(&'t mut self) -> Option<TraversalHandle<'f, 't, G>> {
+        if let Some((products, handle)) = self.next_leo_product() {
+            return Some(self.leo_product_handle(products, handle));
+        }
         while let Some(node) = self.graph_iter.peek() {
+            // Liveness is marked at the node's position, after any padding.
+            self.graph_iter.skip_nop();
             let iter = self.graph_iter;
             let alive = self.liveness[self.graph_iter.handle.usize()];
             println!(
@@ -67,6 +83,16 @@ where
                         }),
                     });
                 }
+                LeoExpanded { first, last } => {
+                    self.leo_expansion = Some(LeoExpansion {
+                        handle: iter.handle,
+                        products: self.bocage.graph.iter_from(first),
+                        last,
+                    });
+                    if let Some((products, handle)) = self.next_leo_product() {
+                        return Some(self.leo_product_handle(products, handle));
+                    }
+                }
                 Sum {
                     nonterminal: symbol,
                     count,
@@ -93,18 +119,118 @@ where
                         item: NullingHandle,
                     });
                 }
-                Evaluated { symbol, .. } => {
+                Evaluated { symbol, values } => {
+                    return Some(TraversalHandle {
+                        iter,
+                        symbol,
+                        item: LeafHandle(values),
+                    });
+                }
+                Error { symbol, skipped } => {
+                    return Some(TraversalHandle {
+                        iter,
+                        symbol,
+                        item: ErrorHandle(skipped),
+                    });
+                }
+                Virtual { symbol, values } => {
                     return Some(TraversalHandle {
                         iter,
                         symbol,
-                        item: LeafHandle,
+                        item: VirtualHandle(values),
                     });
                 }
+                Leo { .. } => unreachable!(),
+            }
+        }
+        None
+    }
+
+    // Returns the next product of the Leo chain that is being traversed, along with
+    // the handle it is evaluated under. The topmost product is given the handle of
+    // the Leo node.
+    fn next_leo_product(&mut self) -> Option<(Iter<'f>, NodeHandle)> {
+        if let Some(ref mut expansion) = self.leo_expansion {
+            while expansion.products.handle <= expansion.last {
+                let products = expansion.products;
+                match expansion.products.next() {
+                    Some(Product { action, .. }) => {
+                        if !self.bocage.is_transparent(action) {
+                            let handle = if products.handle == expansion.last {
+                                expansion.handle
+                            } else {
+                                products.handle
+                            };
+                            return Some((products, handle));
+                        }
+                    }
+                    _ => unreachable!(),
+                }
             }
         }
+        self.leo_expansion = None;
         None
     }
 
+    fn leo_product_handle<'t>(
+        &'t mut self,
+        products: Iter<'f>,
+        handle: NodeHandle,
+    ) -> TraversalHandle<'f, 't, G> {
+        let action = match products.clone().next() {
+            Some(Product { action, .. }) => action,
+            _ => unreachable!(),
+        };
+        TraversalHandle {
+            iter: self.bocage.graph.iter_from(handle),
+            symbol: self.bocage.grammar.borrow().get_lhs(action),
+            item: SumHandle(Products {
+                products: products.take(1),
+                traverse: self,
+            }),
+        }
+    }
+
+    /// Traverses the remaining nodes and returns an iterator over the trees rooted
+    /// at `root`.
+    pub fn trees(&mut self, root: NodeHandle) -> TreeIter {
+        let mut packed = PackedTrees::new();
+        let mut finished = BTreeMap::new();
+        while let Some(mut item) = self.next_node() {
+            match item.item {
+                SumHandle(ref mut products) => {
+                    while let Some(product) = products.next_product() {
+                        let children = product
+                            .factors
+                            .iter()
+                            .map(|&(_sym, handle)| finished.get(&handle).cloned());
+                        packed.push_rule(product.action, children);
+                    }
+                }
+                NullingHandle => {
+                    packed.push_leaf(Tree::Nulling {
+                        symbol: item.symbol,
+                    });
+                }
+                LeafHandle(value) | VirtualHandle(value) => {
+                    packed.push_leaf(Tree::Leaf {
+                        symbol: item.symbol,
+                        value,
+                    });
+                }
+                ErrorHandle(skipped) => {
+                    packed.push_leaf(Tree::Error {
+                        symbol: item.symbol,
+                        skipped,
+                    });
+                }
+            }
+            finished.insert(item.handle(), packed.end_node());
+            item.end_evaluation();
+        }
+        TreeIter::new(packed, finished.get(&root).cloned())
+    }
+
     fn unfold_factors(&mut self, left: NodeHandle, right: Option<NodeHandle>) {
         self.factor_stack.clear();
         self.enqueue_for_unfold(left, right);
@@ -120,9 +246,13 @@ where
                 ) => {
                     self.enqueue_for_unfold(left_factor, right_factor);
                 }
-                (Evaluated { symbol }, handle) => {
+                (Evaluated { symbol, .. }, handle) => {
                     self.factor_stack.push((symbol, handle));
                 }
+                // The topmost product of the chain is transparent.
+                (LeoExpanded { last, .. }, _) => {
+                    self.enqueue_for_unfold(last, None);
+                }
                 _ => unreachable!(),
             }
         }
@@ -151,7 +281,9 @@ pub struct TraversalHandle<'f, 't, G> {
 pub enum HandleVariant<'f, 't, G> {
     SumHandle(Products<'f, 't, G>),
     NullingHandle,
-    LeafHandle,
+    LeafHandle(u32),
+    ErrorHandle(u32),
+    VirtualHandle(u32),
 }
 
 pub struct Products<'f, 't, G> {
@@ -198,8 +330,15 @@ where
 }
 
 impl<'f, 't, G> TraversalHandle<'f, 't, G> {
+    /// Marks the node as evaluated. The rest of the node is padded, so that the graph
+    /// can still be read past it.
     pub fn end_evaluation(&self) {
-        self.iter.vec[self.iter.handle.usize()].set(Tag::SmallLeafTag.to_u16());
+        let start = self.iter.handle.usize();
+        let size = Tag::from_u16(self.iter.vec[start].get()).map_or(1, Tag::size);
+        self.iter.vec[start].set(Tag::SmallLeafTag.to_u16());
+        for cell in &self.iter.vec[start + 1..start + size] {
+            cell.set(Tag::NopTag.to_u16());
+        }
     }
 
     pub fn handle(&self) -> NodeHandle {
@@ -0,0 +1,128 @@
+//! Graphviz output shared by forests.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use cfg::symbol::Symbol;
+
+use forest::node_handle::NodeHandle;
+use grammar::InternalGrammar;
+
+/// Identifies a node in the output. Products of sums and of Leo chains are identified
+/// by their parent and position.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct DotId {
+    handle: NodeHandle,
+    part: Option<u32>,
+}
+
+impl DotId {
+    pub(crate) fn node(handle: NodeHandle) -> Self {
+        DotId { handle, part: None }
+    }
+
+    pub(crate) fn part(handle: NodeHandle, part: u32) -> Self {
+        DotId {
+            handle,
+            part: Some(part),
+        }
+    }
+}
+
+impl fmt::Display for DotId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.part {
+            Some(part) => write!(f, "n{}_{}", self.handle.0, part),
+            None => write!(f, "n{}", self.handle.0),
+        }
+    }
+}
+
+/// Writes nodes and edges of a forest in the dot language.
+pub(crate) struct DotWriter<'a, W: 'a> {
+    out: &'a mut W,
+    grammar: &'a InternalGrammar,
+    pub(crate) show_transparent: bool,
+}
+
+impl<'a, W: Write> DotWriter<'a, W> {
+    pub(crate) fn new(
+        out: &'a mut W,
+        grammar: &'a InternalGrammar,
+        show_transparent: bool,
+    ) -> io::Result<Self> {
+        writeln!(out, "digraph forest {{")?;
+        writeln!(out, "    ordering=out;")?;
+        Ok(DotWriter {
+            out,
+            grammar,
+            show_transparent,
+        })
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        writeln!(self.out, "}}")
+    }
+
+    /// Writes a sum node. Its symbol is internal.
+    pub(crate) fn sum(&mut self, id: DotId, nonterminal: Symbol) -> io::Result<()> {
+        let symbol = self.grammar.to_external(nonterminal);
+        self.node(id, "ellipse", &self.grammar.display_symbol(symbol))
+    }
+
+    /// Writes a product of the rule with the given external id, or of an internal rule.
+    pub(crate) fn product(&mut self, id: DotId, rule: Option<u32>) -> io::Result<()> {
+        match rule {
+            Some(rule) => match self.grammar.rule_name(rule) {
+                Some(name) => self.node(id, "box", &name),
+                None => self.node(id, "box", &format_args!("rule {}", rule)),
+            },
+            None => self.node(id, "box", &"internal"),
+        }
+    }
+
+    pub(crate) fn nulling(&mut self, id: DotId, symbol: Symbol) -> io::Result<()> {
+        let label = format_args!("{} = ε", self.grammar.display_symbol(symbol));
+        self.node(id, "plaintext", &label)
+    }
+
+    pub(crate) fn leaf(
+        &mut self,
+        id: DotId,
+        symbol: Symbol,
+        value: &dyn fmt::Debug,
+    ) -> io::Result<()> {
+        let label = format_args!("{} = {:?}", self.grammar.display_symbol(symbol), value);
+        self.node(id, "plaintext", &label)
+    }
+
+    pub(crate) fn error(&mut self, id: DotId, symbol: Symbol, skipped: u32) -> io::Result<()> {
+        let symbol = self.grammar.display_symbol(symbol);
+        let label = format_args!("{} error, {} skipped", symbol, skipped);
+        self.node(id, "plaintext", &label)
+    }
+
+    pub(crate) fn virtual_leaf(
+        &mut self,
+        id: DotId,
+        symbol: Symbol,
+        value: &dyn fmt::Debug,
+    ) -> io::Result<()> {
+        let symbol = self.grammar.display_symbol(symbol);
+        let label = format_args!("{} virtual = {:?}", symbol, value);
+        self.node(id, "plaintext", &label)
+    }
+
+    pub(crate) fn edge(&mut self, from: DotId, to: DotId) -> io::Result<()> {
+        writeln!(self.out, "    {} -> {};", from, to)
+    }
+
+    fn node(&mut self, id: DotId, shape: &str, label: &dyn fmt::Display) -> io::Result<()> {
+        let label = label.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(
+            self.out,
+            "    {} [shape={}, label=\"{}\"];",
+            id, shape, label
+        )
+    }
+}
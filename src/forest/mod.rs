@@ -1,11 +1,16 @@
 pub mod bocage;
 pub mod compact_bocage;
+mod dot;
 pub mod node_handle;
 pub mod null_forest;
+pub mod one_parse;
+pub mod tree;
 
-pub use self::bocage::Bocage;
+pub use self::bocage::{Bocage, LeafPayload};
 pub use self::compact_bocage::CompactBocage;
 pub use self::null_forest::NullForest;
+pub use self::one_parse::OneParse;
+pub use self::tree::{Tree, TreeCount, TreeIter};
 
 use cfg::Symbol;
 use std::fmt;
@@ -27,5 +32,42 @@ pub trait Forest {
 
     fn leaf(&mut self, token: Symbol, pos: u32, value: Self::LeafValue) -> Self::NodeRef;
 
+    /// Creates a leaf for an error token that stands for `skipped` input tokens. Forests
+    /// that don't tell errors apart create a plain leaf with the default value.
+    fn error(&mut self, token: Symbol, pos: u32, _skipped: u32) -> Self::NodeRef
+    where
+        Self::LeafValue: Default,
+    {
+        self.leaf(token, pos, Self::LeafValue::default())
+    }
+
+    /// Creates a leaf for a token that was injected by the parser, rather than read from input.
+    /// Forests that don't tell virtual tokens apart create a plain leaf.
+    fn virtual_leaf(&mut self, token: Symbol, pos: u32, value: Self::LeafValue) -> Self::NodeRef {
+        self.leaf(token, pos, value)
+    }
+
     fn nulling(&self, token: Symbol) -> Self::NodeRef;
+
+    /// Creates a link of a Leo chain for the medial item at `dot` with the given left factor.
+    /// The link is followed by `up`, which leads towards the topmost item of the chain.
+    fn leo_link(
+        &mut self,
+        dot: u32,
+        left: Self::NodeRef,
+        up: Option<Self::NodeRef>,
+    ) -> Self::NodeRef;
+
+    /// Creates a node for a Leo chain completed by `bottom`. It stands for the completions
+    /// that the recognizer skipped, up to the topmost item of the chain.
+    fn leo(&mut self, chain: Self::NodeRef, bottom: Self::NodeRef) -> Self::NodeRef;
+
+    /// Returns the length of the forest's graph. Forests that can't be truncated return 0.
+    fn graph_len(&self) -> usize {
+        0
+    }
+
+    /// Removes nodes created after the graph had the given length. By default, nodes are
+    /// kept, which leaves them unreachable after a rollback.
+    fn truncate_graph(&mut self, _len: usize) {}
 }
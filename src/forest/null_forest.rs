@@ -15,8 +15,16 @@ impl Forest for NullForest {
     #[inline(always)]
     fn leaf(&mut self, _: Symbol, _: u32, _: ()) {}
     #[inline(always)]
+    fn error(&mut self, _: Symbol, _: u32, _: u32) {}
+    #[inline(always)]
+    fn virtual_leaf(&mut self, _: Symbol, _: u32, _: ()) {}
+    #[inline(always)]
     fn nulling(&self, _: Symbol) {}
     #[inline(always)]
+    fn leo_link(&mut self, _: u32, _: (), _: Option<()>) {}
+    #[inline(always)]
+    fn leo(&mut self, _: (), _: ()) {}
+    #[inline(always)]
     fn begin_sum(&mut self) {}
     #[inline(always)]
     fn push_summand(&mut self, _item: CompletedItem<Self::NodeRef>) {}
@@ -24,4 +32,10 @@ impl Forest for NullForest {
     fn sum(&mut self, _lhs_sym: Symbol, _origin: u32) -> Self::NodeRef {
         ()
     }
+    #[inline(always)]
+    fn graph_len(&self) -> usize {
+        0
+    }
+    #[inline(always)]
+    fn truncate_graph(&mut self, _len: usize) {}
 }
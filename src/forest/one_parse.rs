@@ -0,0 +1,135 @@
+use cfg::symbol::Symbol;
+
+use forest::Forest;
+use item::CompletedItem;
+
+/// A policy that chooses one summand of every ambiguous completion.
+pub trait SummandPolicy<N> {
+    /// Returns `true` if the candidate should replace the chosen summand.
+    fn prefer(&mut self, chosen: &CompletedItem<N>, candidate: &CompletedItem<N>) -> bool;
+}
+
+/// A policy that keeps the first summand.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FirstSummand;
+
+impl<N> SummandPolicy<N> for FirstSummand {
+    fn prefer(&mut self, _chosen: &CompletedItem<N>, _candidate: &CompletedItem<N>) -> bool {
+        false
+    }
+}
+
+/// A forest that keeps exactly one summand of every completion, so that it holds
+/// a single parse tree. Summands are chosen by the policy. The forest grows with
+/// the number of completions rather than the number of their alternatives.
+pub struct OneParse<F, P>
+where
+    F: Forest,
+{
+    /// The underlying forest.
+    pub forest: F,
+    policy: P,
+    chosen: Option<CompletedItem<F::NodeRef>>,
+}
+
+impl<F, P> OneParse<F, P>
+where
+    F: Forest,
+    P: SummandPolicy<F::NodeRef>,
+{
+    pub fn new(forest: F, policy: P) -> Self {
+        OneParse {
+            forest,
+            policy,
+            chosen: None,
+        }
+    }
+
+    /// Returns the underlying forest.
+    pub fn into_inner(self) -> F {
+        self.forest
+    }
+}
+
+impl<F, P> Forest for OneParse<F, P>
+where
+    F: Forest,
+    P: SummandPolicy<F::NodeRef>,
+{
+    type NodeRef = F::NodeRef;
+    type LeafValue = F::LeafValue;
+
+    const FOREST_BYTES_PER_RECOGNIZER_BYTE: usize = F::FOREST_BYTES_PER_RECOGNIZER_BYTE;
+
+    #[inline]
+    fn begin_sum(&mut self) {
+        self.chosen = None;
+        self.forest.begin_sum();
+    }
+
+    #[inline]
+    fn push_summand(&mut self, item: CompletedItem<Self::NodeRef>) {
+        let replace = match self.chosen {
+            Some(ref chosen) => self.policy.prefer(chosen, &item),
+            None => true,
+        };
+        if replace {
+            self.chosen = Some(item);
+        }
+    }
+
+    #[inline]
+    fn sum(&mut self, lhs_sym: Symbol, origin: u32) -> Self::NodeRef {
+        let chosen = self.chosen.take().expect("empty sum");
+        self.forest.push_summand(chosen);
+        self.forest.sum(lhs_sym, origin)
+    }
+
+    #[inline]
+    fn leaf(&mut self, token: Symbol, pos: u32, value: Self::LeafValue) -> Self::NodeRef {
+        self.forest.leaf(token, pos, value)
+    }
+
+    #[inline]
+    fn error(&mut self, token: Symbol, pos: u32, skipped: u32) -> Self::NodeRef
+    where
+        Self::LeafValue: Default,
+    {
+        self.forest.error(token, pos, skipped)
+    }
+
+    #[inline]
+    fn virtual_leaf(&mut self, token: Symbol, pos: u32, value: Self::LeafValue) -> Self::NodeRef {
+        self.forest.virtual_leaf(token, pos, value)
+    }
+
+    #[inline]
+    fn nulling(&self, token: Symbol) -> Self::NodeRef {
+        self.forest.nulling(token)
+    }
+
+    #[inline]
+    fn leo_link(
+        &mut self,
+        dot: u32,
+        left: Self::NodeRef,
+        up: Option<Self::NodeRef>,
+    ) -> Self::NodeRef {
+        self.forest.leo_link(dot, left, up)
+    }
+
+    #[inline]
+    fn leo(&mut self, chain: Self::NodeRef, bottom: Self::NodeRef) -> Self::NodeRef {
+        self.forest.leo(chain, bottom)
+    }
+
+    #[inline]
+    fn graph_len(&self) -> usize {
+        self.forest.graph_len()
+    }
+
+    #[inline]
+    fn truncate_graph(&mut self, len: usize) {
+        self.forest.truncate_graph(len)
+    }
+}
@@ -0,0 +1,237 @@
+use bit_vec::BitVec;
+use cfg::symbol::Symbol;
+use num::BigUint;
+
+use forest::node_handle::NodeHandle;
+
+/// A single derivation taken from a forest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Tree<L = u32> {
+    /// An application of the rule with the given external id.
+    Rule { action: u32, children: Vec<Tree<L>> },
+    /// A token with its value.
+    Leaf { symbol: Symbol, value: L },
+    /// An error token that stands for `skipped` input tokens.
+    Error { symbol: Symbol, skipped: u32 },
+    /// A nulling symbol, whose derivation is not kept in the forest.
+    Nulling { symbol: Symbol },
+}
+
+#[derive(Clone, Debug)]
+enum Alternative<L> {
+    Rule { action: u32, children: (u32, u32) },
+    Leaf(Tree<L>),
+}
+
+/// Alternatives of forest nodes, collected during a traversal. Nodes are numbered in
+/// the order of traversal.
+#[derive(Clone, Debug)]
+pub(crate) struct PackedTrees<L> {
+    // Ranges of alternatives for every node.
+    nodes: Vec<(u32, u32)>,
+    alternatives: Vec<Alternative<L>>,
+    children: Vec<u32>,
+    // The start of alternatives for the current node.
+    node_start: u32,
+}
+
+impl<L> PackedTrees<L> {
+    pub(crate) fn new() -> Self {
+        PackedTrees {
+            nodes: vec![],
+            alternatives: vec![],
+            children: vec![],
+            node_start: 0,
+        }
+    }
+
+    /// Adds an alternative to the current node. It is dropped if any child was not
+    /// finished before, or has no trees. Thus, cycles can't occur.
+    pub(crate) fn push_rule<I>(&mut self, action: u32, children: I)
+    where
+        I: IntoIterator<Item = Option<u32>>,
+    {
+        let start = self.children.len();
+        for child in children {
+            match child {
+                Some(child) if self.has_trees(child) => {
+                    self.children.push(child);
+                }
+                _ => {
+                    self.children.truncate(start);
+                    return;
+                }
+            }
+        }
+        self.alternatives.push(Alternative::Rule {
+            action,
+            children: (start as u32, self.children.len() as u32),
+        });
+    }
+
+    /// Adds a leaf alternative to the current node.
+    pub(crate) fn push_leaf(&mut self, leaf: Tree<L>) {
+        self.alternatives.push(Alternative::Leaf(leaf));
+    }
+
+    /// Finishes the current node and returns its number.
+    pub(crate) fn end_node(&mut self) -> u32 {
+        let end = self.alternatives.len() as u32;
+        self.nodes.push((self.node_start, end));
+        self.node_start = end;
+        self.nodes.len() as u32 - 1
+    }
+
+    fn has_trees(&self, node: u32) -> bool {
+        self.nodes
+            .get(node as usize)
+            .is_some_and(|&(start, end)| start < end)
+    }
+
+    fn alternatives(&self, node: u32) -> &[Alternative<L>] {
+        let (start, end) = self.nodes[node as usize];
+        &self.alternatives[start as usize..end as usize]
+    }
+}
+
+/// An iterator over distinct trees of a forest. Trees are built one at a time, in the
+/// order of choices made at ambiguous nodes.
+pub struct TreeIter<L = u32> {
+    packed: PackedTrees<L>,
+    root: u32,
+    // The chosen alternative and the number of alternatives at every ambiguous node,
+    // in the order of their appearance in the current tree.
+    choices: Vec<(u32, u32)>,
+    remaining: Option<usize>,
+    done: bool,
+}
+
+impl<L: Clone> TreeIter<L> {
+    pub(crate) fn new(packed: PackedTrees<L>, root: Option<u32>) -> Self {
+        let done = !root.is_some_and(|root| packed.has_trees(root));
+        TreeIter {
+            packed,
+            root: root.unwrap_or(0),
+            choices: vec![],
+            remaining: None,
+            done,
+        }
+    }
+
+    /// Limits the number of trees to be returned.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.remaining = Some(limit);
+        self
+    }
+
+    fn build(&mut self, node: u32, choice_idx: &mut usize) -> Tree<L> {
+        let count = self.packed.alternatives(node).len() as u32;
+        let choice = if count > 1 {
+            if *choice_idx == self.choices.len() {
+                self.choices.push((0, count));
+            }
+            *choice_idx += 1;
+            self.choices[*choice_idx - 1].0
+        } else {
+            0
+        };
+        match self.packed.alternatives(node)[choice as usize].clone() {
+            Alternative::Rule {
+                action,
+                children: (start, end),
+            } => {
+                let children = (start..end)
+                    .map(|i| {
+                        let child = self.packed.children[i as usize];
+                        self.build(child, choice_idx)
+                    })
+                    .collect();
+                Tree::Rule { action, children }
+            }
+            Alternative::Leaf(leaf) => leaf,
+        }
+    }
+
+    // Moves on to the next combination of choices, from the innermost one.
+    fn advance(&mut self) {
+        while let Some(&mut (ref mut choice, count)) = self.choices.last_mut() {
+            if *choice + 1 < count {
+                *choice += 1;
+                return;
+            }
+            self.choices.pop();
+        }
+        self.done = true;
+    }
+}
+
+impl<L: Clone> Iterator for TreeIter<L> {
+    type Item = Tree<L>;
+
+    fn next(&mut self) -> Option<Tree<L>> {
+        if self.done || self.remaining == Some(0) {
+            return None;
+        }
+        if let Some(ref mut remaining) = self.remaining {
+            *remaining -= 1;
+        }
+        let mut choice_idx = 0;
+        let root = self.root;
+        let tree = self.build(root, &mut choice_idx);
+        self.advance();
+        Some(tree)
+    }
+}
+
+/// The number of trees in a forest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeCount {
+    Finite(BigUint),
+    /// A cycle is reachable from the root, so the number of trees is unbounded.
+    Infinite,
+}
+
+/// Counts trees bottom-up. `alternatives` lists the alternatives of a node, each with up
+/// to two factors. A leaf has a single alternative without factors.
+pub(crate) fn count_trees<F>(root: NodeHandle, graph_len: usize, mut alternatives: F) -> TreeCount
+where
+    F: FnMut(NodeHandle, &mut Vec<[Option<NodeHandle>; 2]>),
+{
+    let mut counts: Vec<Option<BigUint>> = vec![None; graph_len];
+    // Nodes that are being counted. Reaching one of them again means a cycle.
+    let mut in_progress = BitVec::from_elem(graph_len, false);
+    let mut stack = vec![(root, false)];
+    let mut scratch = vec![];
+    while let Some((node, factors_counted)) = stack.pop() {
+        let idx = node.usize();
+        scratch.clear();
+        if factors_counted {
+            alternatives(node, &mut scratch);
+            let count = scratch
+                .iter()
+                .map(|factors| {
+                    factors
+                        .iter()
+                        .flatten()
+                        .map(|factor| counts[factor.usize()].as_ref().unwrap())
+                        .product::<BigUint>()
+                })
+                .sum();
+            counts[idx] = Some(count);
+            in_progress.set(idx, false);
+        } else if counts[idx].is_none() {
+            if in_progress[idx] {
+                return TreeCount::Infinite;
+            }
+            in_progress.set(idx, true);
+            stack.push((node, true));
+            alternatives(node, &mut scratch);
+            for &factor in scratch.iter().flat_map(|factors| factors.iter().flatten()) {
+                if counts[factor.usize()].is_none() {
+                    stack.push((factor, false));
+                }
+            }
+        }
+    }
+    TreeCount::Finite(counts[root.usize()].take().unwrap())
+}
@@ -1,12 +1,17 @@
+use std::fmt;
 use std::iter;
 
 use bit_matrix::BitMatrix;
 use cfg::{ContextFreeRef, GrammarRule, Symbol};
+use cfg::classification::cyclical::Cycles;
+use cfg::classification::useful::Usefulness;
 use cfg::rule::container::RuleContainer;
 use cfg::remap::Mapping;
+use cfg::sequence::Separator;
 use cfg::prediction::{FirstSetsCollector, FollowSets};
 use optional::Optioned;
 
+use error::GrammarError;
 use item::Dot;
 
 pub use cfg::earley::{Grammar, BinarizedGrammar};
@@ -105,6 +110,18 @@ pub struct InternalGrammar {
     // Mapping between external and internal symbols.
     sym_maps: Mapping,
     nulling_intermediate_rules: Vec<NullingIntermediateRule>,
+    // Ranks of external rules.
+    #[serde(default)]
+    rule_ranks: Vec<i32>,
+    // Names of external symbols and rules, for diagnostics.
+    #[serde(default)]
+    symbol_names: Vec<Option<String>>,
+    #[serde(default)]
+    rule_names: Vec<Option<String>>,
+    // Sides of external rules, for diagnostics. Grammars that were binarized elsewhere
+    // don't have them.
+    #[serde(default)]
+    external_rules: Vec<Option<ExternalRule>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
@@ -117,6 +134,7 @@ pub struct InternalGrammarSize {
 
 pub(in super) type ExternalDottedRule = (u32, u32);
 type ExternalOrigin = Option<u32>;
+type ExternalRule = (Symbol, Vec<Symbol>);
 type EventId = Optioned<u32>;
 type MinimalDistance = Optioned<u32>;
 pub(in super) type Event = (EventId, MinimalDistance);
@@ -124,13 +142,135 @@ type NullingEliminated = Option<(Symbol, bool)>;
 type NullingIntermediateRule = (Symbol, Symbol, Symbol);
 type CompletionTable = Vec<Vec<PredictionTransition>>;
 
+// Forests keep a node for each nulling symbol, so their count is limited.
+pub(in super) const MAX_NULLING_SYMBOLS: usize = 1 << 20;
+
 impl InternalGrammar {
     fn new() -> Self {
         Self::default()
     }
 
     pub fn from_grammar(grammar: &Grammar) -> Self {
-        Self::from_binarized_grammar(grammar.binarize())
+        let mut result = Self::from_binarized_grammar(grammar.binarize());
+        result.external_rules = external_rules(grammar);
+        result
+    }
+
+    /// Processes a grammar like `from_grammar` and names its symbols. The grammar from `cfg`
+    /// doesn't keep names, so they are given in a list that is indexed by external symbols.
+    pub fn from_grammar_with_names(grammar: &Grammar, names: &[&str]) -> Self {
+        let mut result = Self::from_grammar(grammar);
+        result.set_symbol_names(names);
+        result
+    }
+
+    /// Processes and names a grammar like `from_grammar_with_names`, but checks it first
+    /// like `try_from_grammar`. Errors are displayed with these names by
+    /// `GrammarError::display`.
+    pub fn try_from_grammar_with_names(
+        grammar: &Grammar,
+        names: &[&str],
+    ) -> Result<Self, GrammarError> {
+        let mut result = Self::try_from_grammar(grammar)?;
+        result.set_symbol_names(names);
+        Ok(result)
+    }
+
+    /// Processes a grammar like `from_grammar`, but checks it first. Useless rules are
+    /// reported rather than removed.
+    pub fn try_from_grammar(grammar: &Grammar) -> Result<Self, GrammarError> {
+        let external_rules = external_rules(grammar);
+        // Symbols added by binarization and by rewriting of sequences are not reported.
+        let num_external_syms = grammar.sym_source().num_syms();
+        let external = |symbols: Vec<Symbol>| -> Vec<Symbol> {
+            let mut symbols: Vec<_> = symbols
+                .into_iter()
+                .filter(|sym| sym.usize() < num_external_syms)
+                .collect();
+            symbols.sort();
+            symbols.dedup();
+            symbols
+        };
+        {
+            // Binarization keeps a single empty rule per symbol.
+            let mut empty: Vec<_> = grammar
+                .rules()
+                .filter(|rule| rule.rhs().is_empty())
+                .map(|rule| rule.lhs())
+                .chain(
+                    grammar
+                        .sequence_rules()
+                        .iter()
+                        .filter(|seq| match seq.separator {
+                            Separator::Liberal(_) => false,
+                            _ => seq.start == 0,
+                        })
+                        .map(|seq| seq.lhs),
+                )
+                .collect();
+            empty.sort();
+            let duplicates: Vec<_> = empty
+                .windows(2)
+                .filter(|pair| pair[0] == pair[1])
+                .map(|pair| pair[0])
+                .collect();
+            if !duplicates.is_empty() {
+                return Err(GrammarError::DuplicateEmptyRule(external(duplicates)));
+            }
+        }
+        let mut grammar = grammar.binarize();
+        // The grammar is empty when the start symbol is not set or has no rules.
+        if grammar.is_empty() {
+            return Err(GrammarError::MissingStart);
+        }
+        let start = grammar.start();
+        {
+            let usefulness = Usefulness::new(&mut *grammar).reachable([start]);
+            let mut unproductive = vec![];
+            let mut inaccessible = vec![];
+            for useless in usefulness.useless_rules() {
+                let rule = useless.rule;
+                let symbols = iter::once(rule.lhs()).chain(rule.rhs().iter().cloned());
+                unproductive.extend(symbols.filter(|&sym| !usefulness.productivity(sym)));
+                if useless.unreachable {
+                    inaccessible.push(rule.lhs());
+                }
+            }
+            if !unproductive.is_empty() {
+                return Err(GrammarError::Unproductive(external(unproductive)));
+            }
+            if !inaccessible.is_empty() {
+                return Err(GrammarError::Inaccessible(external(inaccessible)));
+            }
+        }
+        let (mut grammar, nulling) = grammar.eliminate_nulling();
+        {
+            let mut participants = vec![];
+            {
+                let cycles = Cycles::new(&mut *grammar);
+                if !cycles.cycle_free() {
+                    participants.extend(cycles.cycle_participants().map(|rule| rule.lhs()));
+                }
+            }
+            // The unit derivation matrix doesn't cover rules of the form `A ::= A`.
+            participants.extend(
+                grammar
+                    .rules()
+                    .filter(|rule| rule.rhs() == [rule.lhs()])
+                    .map(|rule| rule.lhs()),
+            );
+            if !participants.is_empty() {
+                return Err(GrammarError::Cycle(external(participants)));
+            }
+        }
+        grammar.wrap_start();
+        let mut result = Self::from_processed_grammar(grammar, &nulling);
+        result.external_rules = external_rules;
+        let nulling_symbol_count = result.max_nulling_symbol().unwrap_or(0);
+        if result.num_rules() > Dot::MAX as usize || nulling_symbol_count >= MAX_NULLING_SYMBOLS {
+            return Err(GrammarError::TooLarge);
+        }
+        Ok(result)
     }
 
     pub fn from_binarized_grammar(grammar: BinarizedGrammar) -> Self {
@@ -456,6 +596,11 @@ impl InternalGrammar {
         [&self.trace_rhs[0][..], &self.trace_rhs[1][..], &self.trace_rhs[2][..]]
     }
 
+    #[inline]
+    pub(in super) fn get_rhs0(&self, dot: Dot) -> Option<Symbol> {
+        self.rhs0[dot as usize]
+    }
+
     #[inline]
     pub(in super) fn get_rhs1(&self, dot: Dot) -> Option<Symbol> {
         self.rhs1[dot as usize]
@@ -469,16 +614,94 @@ impl InternalGrammar {
         }
     }
 
+    /// Returns the symbols of a product's factors, including the nulling factor that is
+    /// added to products of rules with an eliminated nulling symbol.
+    pub(in super) fn factor_symbols(&self, dot: Dot) -> [Option<Symbol>; 2] {
+        let rhs0 = self.get_rhs0(dot);
+        match self.nulling(dot) {
+            Some((sym, true)) => [rhs0, Some(sym)],
+            Some((sym, false)) => [Some(sym), rhs0],
+            None => [rhs0, self.get_rhs1(dot)],
+        }
+    }
+
     #[inline]
     pub(in super) fn rhs1(&self) -> &[Option<Symbol>] {
         &self.rhs1[..]
     }
 
+    /// Sets the rank of a rule, which is identified by its external rule ID. Alternatives
+    /// of higher rank are preferred by `RankOrder`. Ranks are zero unless set.
+    pub fn set_rule_rank(&mut self, rule: u32, rank: i32) {
+        let rule = rule as usize;
+        if self.rule_ranks.len() <= rule {
+            self.rule_ranks.resize(rule + 1, 0);
+        }
+        self.rule_ranks[rule] = rank;
+    }
+
+    /// Returns the rank of the external rule that the given dot belongs to.
+    pub fn rule_rank(&self, dot: Dot) -> i32 {
+        let rule = self.eval.get(dot as usize).cloned().and_then(|origin| origin);
+        rule.and_then(|rule| self.rule_ranks.get(rule as usize).cloned()).unwrap_or(0)
+    }
+
+    /// Sets the name of an external symbol. Names are used in diagnostics.
+    pub fn set_symbol_name<S: Into<String>>(&mut self, symbol: Symbol, name: S) {
+        set_name(&mut self.symbol_names, symbol.usize(), name.into());
+    }
+
+    fn set_symbol_names(&mut self, names: &[&str]) {
+        for (i, &name) in names.iter().enumerate() {
+            self.set_symbol_name(Symbol::from(i), name);
+        }
+    }
+
+    /// Returns the name of an external symbol, if it has one.
+    pub fn symbol_name(&self, symbol: Symbol) -> Option<&str> {
+        get_name(&self.symbol_names, symbol.usize())
+    }
+
+    /// Sets the name of a rule, which is identified by its external rule ID. Names are used
+    /// in diagnostics.
+    pub fn set_rule_name<S: Into<String>>(&mut self, rule: u32, name: S) {
+        set_name(&mut self.rule_names, rule as usize, name.into());
+    }
+
+    /// Returns the name of a rule, which is identified by its external rule ID.
+    pub fn rule_name(&self, rule: u32) -> Option<&str> {
+        get_name(&self.rule_names, rule as usize)
+    }
+
+    /// Returns the LHS and RHS of an external rule, if the grammar was processed from them.
+    /// Sequences have their element and separator as the RHS.
+    pub(in super) fn external_rule(&self, rule: u32) -> Option<(Symbol, &[Symbol])> {
+        match self.external_rules.get(rule as usize) {
+            Some(&Some((lhs, ref rhs))) => Some((lhs, &rhs[..])),
+            _ => None,
+        }
+    }
+
+    /// Displays an external symbol by its name, or by its number if it has no name.
+    pub(in super) fn display_symbol(&self, symbol: Symbol) -> DisplaySymbol<'_> {
+        DisplaySymbol {
+            name: self.symbol_name(symbol),
+            symbol,
+        }
+    }
+
     #[inline]
     pub(in super) fn get_lhs(&self, dot: Dot) -> Symbol {
         self.lhs[dot as usize].unwrap()
     }
 
+    /// Checks whether the given internal symbol is a terminal. The end of input is not
+    /// considered a terminal.
+    pub(in super) fn is_terminal(&self, symbol: Symbol) -> bool {
+        // Rules are sorted by their LHS.
+        symbol != self.eof() && self.lhs.binary_search(&Some(symbol)).is_err()
+    }
+
     #[inline]
     pub(in super) fn external_origin(&self, dot: Dot) -> ExternalOrigin {
         self.eval.get(dot as usize).cloned().unwrap()
@@ -507,7 +730,7 @@ impl InternalGrammar {
         if self.sym_maps.to_internal.is_empty() {
             Some(symbol)
         } else {
-            self.sym_maps.to_internal[symbol.usize()]
+            self.sym_maps.to_internal.get(symbol.usize()).cloned().unwrap_or(None)
         }
     }
 
@@ -533,4 +756,103 @@ impl InternalGrammar {
     pub(in super) fn dot_before_eof(&self) -> Dot {
         self.dot_before_eof
     }
+
+    /// Computes a 64-bit FNV-1a hash of the grammar's rules and symbol mapping. It is used to
+    /// check whether saved parse state belongs to this grammar.
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x100_0000_01b3;
+        let sym = |sym: &Symbol| sym.usize() as u32;
+        let opt_sym = |opt: &Option<Symbol>| opt.map_or(!0, |sym| sym.usize() as u32);
+        let header = [
+            sym(&self.start_sym),
+            sym(&self.original_start_sym),
+            sym(&self.eof_sym),
+            self.dot_before_eof,
+            self.has_trivial_derivation as u32,
+            self.size.syms as u32,
+            self.size.rules as u32,
+            self.size.internal_syms as u32,
+            self.size.external_syms as u32,
+        ];
+        let nulling_intermediate = self.nulling_intermediate_rules.iter().flat_map(|rule| {
+            iter::once(sym(&rule.0)).chain(iter::once(sym(&rule.1))).chain(iter::once(sym(&rule.2)))
+        });
+        let words = header.iter().cloned()
+            .chain(self.lhs.iter().map(opt_sym))
+            .chain(self.rhs0.iter().map(opt_sym))
+            .chain(self.rhs1.iter().map(opt_sym))
+            .chain(self.eval.iter().map(|origin| origin.unwrap_or(!0)))
+            .chain(self.sym_maps.to_internal.iter().map(opt_sym))
+            .chain(self.sym_maps.to_external.iter().map(sym))
+            .chain(nulling_intermediate);
+        words.fold(FNV_OFFSET_BASIS, |hash, word| {
+            word.to_le_bytes().iter().fold(hash, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+        })
+    }
+}
+
+// Collects the sides of rules and sequences, which are indexed by their external rule IDs.
+fn external_rules(grammar: &Grammar) -> Vec<Option<ExternalRule>> {
+    let rules = grammar
+        .rules()
+        .map(|rule| (rule.history().origin(), rule.lhs(), rule.rhs().to_vec()));
+    let sequences = grammar.sequence_rules().iter().map(|seq| {
+        let separator = match seq.separator {
+            Separator::Trailing(sep) | Separator::Proper(sep) | Separator::Liberal(sep) => {
+                Some(sep)
+            }
+            Separator::Null => None,
+        };
+        let rhs = iter::once(seq.rhs).chain(separator).collect();
+        (seq.history.origin(), seq.lhs, rhs)
+    });
+    let mut result = vec![];
+    for (origin, lhs, rhs) in rules.chain(sequences) {
+        if let Some(id) = origin {
+            let id = id as usize;
+            if result.len() <= id {
+                result.resize(id + 1, None);
+            }
+            result[id] = Some((lhs, rhs));
+        }
+    }
+    result
+}
+
+fn set_name(names: &mut Vec<Option<String>>, idx: usize, name: String) {
+    if names.len() <= idx {
+        names.resize(idx + 1, None);
+    }
+    names[idx] = Some(name);
+}
+
+fn get_name(names: &[Option<String>], idx: usize) -> Option<&str> {
+    names.get(idx).and_then(|name| name.as_ref()).map(|name| &name[..])
+}
+
+pub(in super) struct DisplaySymbol<'a> {
+    name: Option<&'a str>,
+    symbol: Symbol,
+}
+
+impl<'a> DisplaySymbol<'a> {
+    /// Displays a symbol with a name from the given list, which is indexed by symbols.
+    pub(in super) fn from_names(names: &'a [&'a str], symbol: Symbol) -> Self {
+        DisplaySymbol {
+            name: names.get(symbol.usize()).cloned(),
+            symbol,
+        }
+    }
+}
+
+impl<'a> fmt::Display for DisplaySymbol<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.symbol.usize()),
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use std::cmp::Ordering;
 
+use cfg::Symbol;
+
 pub type Dot = u32;
 pub type Origin = u32;
 
@@ -32,6 +34,31 @@ pub struct CompletedItemLinked<N> {
     pub node: Option<N>,
 }
 
+/// A token that was scanned and completes at a later earleme.
+#[derive(Clone, Copy, Debug)]
+pub struct PendingToken<N> {
+    /// The location where the token was scanned.
+    pub(in super) origin: Origin,
+    /// The internal symbol of the token.
+    pub(in super) symbol: Symbol,
+    /// Leaf bocage node.
+    pub node: N,
+}
+
+/// A Leo item, which memoizes the topmost item of a deterministic chain of right-recursive
+/// completions.
+#[derive(Clone, Copy, Debug)]
+pub struct LeoItem<N> {
+    /// The Earley set of the item.
+    pub(in super) set: Origin,
+    /// The postdot symbol of the only medial item with that symbol in the set.
+    pub(in super) symbol: Symbol,
+    /// The index of the topmost medial item of the chain.
+    pub(in super) top: u32,
+    /// Links of the chain below the topmost item, if any.
+    pub chain: Option<N>,
+}
+
 impl<L> PartialEq for Item<L> {
     fn eq(&self, other: &Self) -> bool {
         (self.origin, self.dot) == (other.origin, other.dot)
@@ -52,6 +79,18 @@ impl<L> Ord for Item<L> {
     }
 }
 
+impl<N> CompletedItem<N> {
+    /// Returns the dot position, which identifies the internal rule.
+    pub fn dot(&self) -> Dot {
+        self.dot
+    }
+
+    /// Returns the origin location.
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+}
+
 impl<L> PartialEq for CompletedItem<L> {
     fn eq(&self, other: &Self) -> bool {
         (self.origin, self.dot) == (other.origin, other.dot)
@@ -14,11 +14,18 @@ extern crate serde_derive;
 extern crate num;
 extern crate num_derive;
 
+pub mod checkpoint;
 pub mod debug;
+pub mod driver;
+pub mod error;
+pub mod eval;
 pub mod events;
 pub mod forest;
 pub mod grammar;
 pub mod item;
 pub mod memory_use;
 pub mod recognizer;
+pub mod recovery;
+pub mod snapshot;
+pub mod stats;
 pub mod binary_heap;
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::mem;
 
 use bit_matrix::BitMatrix;
@@ -53,6 +54,7 @@ where
             grammar,
             // The initial location is 0.
             earleme: 0,
+            pending: BTreeMap::new(),
             // The first Earley set begins at 0 and ends at 0. The second Earley set begins at 0.
             indices: Vec::with_capacity(sets_use),
             current_medial_start: 0,
@@ -60,7 +62,12 @@ where
             predicted: BitMatrix::new(sets_use, grammar.num_syms()),
             medial: Vec::with_capacity(sets_use * ITEMS_PER_SET),
             complete: Vec::with_capacity(complete_use),
-            lookahead_hint: None,
+            lookahead_hint: Vec::new(),
+            leo: Vec::new(),
+            leo_enabled: false,
+            error_recovery: None,
+            checkpoint_depth: 0,
+            stats: None,
         };
         recognizer.indices.push(0);
         recognizer.indices.push(0);
@@ -87,6 +94,7 @@ where
             grammar,
             // The initial location is 0.
             earleme: 0,
+            pending: BTreeMap::new(),
             // The first Earley set begins at 0 and ends at 0. The second Earley set begins at 0.
             indices: Vec::with_capacity(tokens + 1),
             current_medial_start: 0,
@@ -94,7 +102,12 @@ where
             predicted: BitMatrix::new(tokens + 1, grammar.num_syms()),
             medial: Vec::with_capacity(tokens * ITEMS_PER_SET),
             complete: Vec::with_capacity(complete_use),
-            lookahead_hint: None,
+            lookahead_hint: Vec::new(),
+            leo: Vec::new(),
+            leo_enabled: false,
+            error_recovery: None,
+            checkpoint_depth: 0,
+            stats: None,
         };
         recognizer.indices.push(0);
         recognizer.indices.push(0);
@@ -132,6 +145,7 @@ impl<'g> MemoryUse for Recognizer<'g, NullForest> {
             grammar,
             // The initial location is 0.
             earleme: 0,
+            pending: BTreeMap::new(),
             // The first Earley set begins at 0 and ends at 0. The second Earley set begins at 0.
             indices: Vec::with_capacity(sets_use),
             current_medial_start: 0,
@@ -139,7 +153,12 @@ impl<'g> MemoryUse for Recognizer<'g, NullForest> {
             predicted: BitMatrix::new(sets_use, grammar.num_syms()),
             medial: Vec::with_capacity(sets_use * ITEMS_PER_SET),
             complete: Vec::with_capacity(complete_use),
-            lookahead_hint: None,
+            lookahead_hint: Vec::new(),
+            leo: Vec::new(),
+            leo_enabled: false,
+            error_recovery: None,
+            checkpoint_depth: 0,
+            stats: None,
         };
         recognizer.indices.push(0);
         recognizer.indices.push(0);
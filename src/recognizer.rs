@@ -1,13 +1,17 @@
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::ops::Range;
 
 use bit_matrix::BitMatrix;
 use cfg::*;
 
+use error::ParseError;
 use events::{MedialItems, PredictedSymbols};
 use forest::{Forest, NullForest};
 use grammar::InternalGrammar;
-use item::{CompletedItem, CompletedItemLinked, Item, Origin};
+use item::{CompletedItem, CompletedItemLinked, Item, LeoItem, Origin, PendingToken};
+use recovery::ErrorRecovery;
+use stats::ParseStats;
 // use policy::{PerformancePolicy, NullPerformancePolicy};
 
 /// The recognizer implements the Earley algorithm. It parses the given input according
@@ -58,7 +62,28 @@ where
     // The input location.
     pub(super) earleme: usize,
 
-    pub(super) lookahead_hint: Option<Option<Symbol>>,
+    // Tokens that span several earlemes, keyed by the earleme where they end. Earlier sets
+    // are not removed while a token is pending.
+    pub(super) pending: BTreeMap<usize, Vec<PendingToken<F::NodeRef>>>,
+
+    // Symbols that may follow the current set, where `None` stands for the end of input.
+    // Empty when there is no hint.
+    pub(super) lookahead_hint: Vec<Option<Symbol>>,
+
+    // Leo items, sorted by their set and symbol.
+    pub(super) leo: Vec<LeoItem<F::NodeRef>>,
+    // Whether Leo items are created.
+    pub(super) leo_enabled: bool,
+
+    // Configuration of the error recovery mode.
+    pub(super) error_recovery: Option<ErrorRecovery>,
+
+    // The number of outstanding checkpoints. Earlier sets are not removed while
+    // a checkpoint is outstanding.
+    pub(super) checkpoint_depth: usize,
+
+    // Statistics, collected when enabled.
+    pub(super) stats: Option<ParseStats>,
 }
 
 impl<'g, F> Recognizer<'g, F>
@@ -86,16 +111,29 @@ where
             grammar,
             // The initial location is 0.
             earleme: 0,
+            pending: BTreeMap::new(),
             indices: Vec::new(),
             current_medial_start: 0,
             // Reserve some capacity for vectors.
             predicted: BitMatrix::new(0, grammar.num_syms()),
             medial: Vec::new(),
             complete: Vec::new(),
-            lookahead_hint: None,
+            lookahead_hint: Vec::new(),
+            leo: Vec::new(),
+            leo_enabled: false,
+            error_recovery: None,
+            checkpoint_depth: 0,
+            stats: None,
         }
     }
 
+    /// Enables or disables Leo's optimization, which lets right recursion be recognized
+    /// in linear time. The forest expands the skipped completions on demand. Takes effect
+    /// from the next Earley set.
+    pub fn set_leo(&mut self, enabled: bool) {
+        self.leo_enabled = enabled;
+    }
+
     pub(super) fn initialize(&mut self) {
         // The first Earley set begins at 0 and ends at 0.
         self.indices.push(0);
@@ -131,10 +169,98 @@ where
         }
     }
 
+    /// Reads a token that ends `len` earlemes after the current location. Creates a leaf
+    /// bocage node with the given value. The token is completed when the parse reaches
+    /// its end.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `len` is zero.
+    pub fn scan_with_length(&mut self, symbol: Symbol, value: F::LeafValue, len: usize) {
+        assert!(len > 0, "token length must be positive");
+        if len == 1 {
+            self.scan(symbol, value);
+        } else if let Some(internal) = self.grammar.to_internal(symbol) {
+            let earleme = self.earleme as Origin;
+            let end = self.earleme + len;
+            let node = self.forest.leaf(symbol, end as u32, value);
+            self.pending.entry(end).or_default().push(PendingToken {
+                origin: earleme,
+                symbol: internal,
+                node,
+            });
+        }
+    }
+
+    /// Reads a token if it can be accepted at the current location. Otherwise, leaves
+    /// the recognizer intact and returns an error that describes the location.
+    pub fn try_scan(&mut self, symbol: Symbol, value: F::LeafValue) -> Result<(), ParseError> {
+        if self.accepts(symbol) {
+            self.scan(symbol, value);
+            Ok(())
+        } else {
+            Err(self.parse_error(Some(symbol)))
+        }
+    }
+
+    /// Reads a set of alternative tokens that the lexer found at the current location.
+    /// Tokens that can't be accepted are ignored, and the grammar chooses between the rest.
+    /// Returns the number of accepted tokens.
+    ///
+    /// This is equivalent to calling `scan` for each accepted token before advancing the parse.
+    pub fn scan_alternatives<I>(&mut self, alternatives: I) -> usize
+    where
+        I: IntoIterator<Item = (Symbol, F::LeafValue)>,
+    {
+        let mut accepted = 0;
+        for (symbol, value) in alternatives {
+            if self.accepts(symbol) {
+                self.scan(symbol, value);
+                accepted += 1;
+            }
+        }
+        accepted
+    }
+
+    /// Checks whether a token can be accepted at the current location.
+    pub fn accepts(&self, symbol: Symbol) -> bool {
+        match self.grammar.to_internal(symbol) {
+            Some(internal) => self.predicted[self.earleme].get(internal.usize()),
+            None => false,
+        }
+    }
+
+    /// Gives a hint about the token that follows the current location, or the end of input
+    /// when `None`. Completions that can't be followed by it are skipped. The hint applies
+    /// until the parse is advanced, so it should be given before scanning.
     #[inline]
     pub fn lookahead_hint(&mut self, lookahead: Option<Symbol>) {
-        let to_internal = |sym| self.grammar.to_internal(sym).unwrap();
-        self.lookahead_hint = Some(lookahead.map(to_internal));
+        self.lookahead_hints(Some(lookahead));
+    }
+
+    /// Gives a hint about a set of alternative tokens that follow the current location.
+    /// Completions that can't be followed by any of them are skipped. An empty set
+    /// removes the hint. Symbols that the grammar doesn't use are ignored, because
+    /// they can't be scanned.
+    pub fn lookahead_hints<I>(&mut self, lookahead: I)
+    where
+        I: IntoIterator<Item = Option<Symbol>>,
+    {
+        let grammar = self.grammar;
+        self.lookahead_hint.clear();
+        self.lookahead_hint
+            .extend(lookahead.into_iter().filter_map(|sym| match sym {
+                Some(sym) => grammar.to_internal(sym).map(Some),
+                None => Some(None),
+            }));
+    }
+
+    /// Checks whether the lookahead hint allows a completion of the given symbol.
+    #[inline]
+    fn hint_can_follow(&self, before: Symbol) -> bool {
+        self.lookahead_hint
+            .iter()
+            .any(|&hint| self.grammar.can_follow(before, hint))
     }
 
     /// Advances the parse. Calling this method may set the finished node, which can be accessed
@@ -151,10 +277,26 @@ where
         }
     }
 
+    /// Advances the parse. Returns an error that describes the current location
+    /// when the recognizer is exhausted.
+    pub fn try_end_earleme(&mut self) -> Result<(), ParseError> {
+        if self.end_earleme() {
+            Ok(())
+        } else {
+            Err(self.parse_error(None))
+        }
+    }
+
     /// Advances the parse. Omits the completion pass, which should be done through
     /// the `completions` method. Keep in mind that calling this method may not set
     /// the finished node, which should be tracked externally.
     pub fn advance_without_completion(&mut self) {
+        if let Some(ref mut stats) = self.stats {
+            stats
+                .items_per_set
+                .push(self.medial.len() - self.current_medial_start);
+            stats.record_graph_len(self.forest.graph_len());
+        }
         self.sort_medial_items();
         self.remove_unary_medial_items();
         self.remove_unreachable_sets();
@@ -162,16 +304,33 @@ where
         // `earleme` is now at least 1.
         // Prediction pass.
         self.prediction_pass();
+        if self.leo_enabled {
+            self.leo_pass();
+        }
         // Store the index.
         self.current_medial_start = self.medial.len();
         self.indices.push(self.current_medial_start);
+        // Complete tokens that end at the next earleme.
+        self.complete_pending_tokens();
+    }
+
+    /// Completes pending tokens that end at the next earleme, as if they were scanned
+    /// at the current location.
+    fn complete_pending_tokens(&mut self) {
+        if let Some(tokens) = self.pending.remove(&(self.earleme + 1)) {
+            for token in tokens {
+                self.complete(token.origin, token.symbol, token.node);
+            }
+        }
     }
 
     /// Checks whether the recognizer is exhausted. The recognizer is exhausted when it can't accept
     /// more input.
     #[inline]
     pub fn is_exhausted(&self) -> bool {
-        self.medial.len() == self.current_medial_start && self.complete.is_empty()
+        self.medial.len() == self.current_medial_start
+            && self.complete.is_empty()
+            && self.pending.is_empty()
     }
 
     /// Sorts medial items with deduplication.
@@ -194,6 +353,9 @@ where
                 break;
             }
             self.medial.pop();
+            if let Some(ref mut stats) = self.stats {
+                stats.unary_items_removed += 1;
+            }
         }
     }
 
@@ -205,7 +367,7 @@ where
             .max()
             .unwrap_or(self.earleme);
         let diff = self.earleme - max_origin;
-        if diff <= 1 {
+        if diff <= 1 || self.checkpoint_depth > 0 || !self.pending.is_empty() {
             return;
         }
         // | 0 | 1 | 2 | 3 |
@@ -216,6 +378,9 @@ where
         //   ^ m = 0
         // | 0 | 1 | 2 |
         let drop = diff - 1;
+        if let Some(ref mut stats) = self.stats {
+            stats.sets_dropped += drop;
+        }
         let new_medial_start = self.indices[self.indices.len() - 1 - drop];
         self.indices.truncate(self.indices.len() - drop);
         let current_medial_length = self.medial.len() - self.current_medial_start;
@@ -226,6 +391,12 @@ where
             .truncate(new_medial_start as usize + current_medial_length);
         self.current_medial_start = new_medial_start as usize;
         self.earleme -= drop;
+        let leo_len = self
+            .leo
+            .iter()
+            .take_while(|leo| (leo.set as usize) < self.earleme)
+            .count();
+        self.leo.truncate(leo_len);
         self.predicted.truncate(self.earleme + 1);
         for dst in self.predicted[self.earleme].iter_mut() {
             *dst = 0;
@@ -257,6 +428,63 @@ where
         }
     }
 
+    /// Creates Leo items for postdot symbols that only one item in the latest set can
+    /// complete.
+    fn leo_pass(&mut self) {
+        let set_id = self.earleme as Origin;
+        let mut start = self.current_medial_start;
+        while start < self.medial.len() {
+            let item = self.medial[start];
+            let symbol = self.grammar.get_rhs1(item.dot).unwrap();
+            let end = start
+                + self.medial[start..]
+                    .iter()
+                    .take_while(|ei| self.grammar.get_rhs1(ei.dot) == Some(symbol))
+                    .count();
+            if end - start == 1 && !self.predicts_completion_of(set_id, symbol) {
+                let lhs = self.grammar.get_lhs(item.dot);
+                let leo_item = match self.leo_item(item.origin, lhs) {
+                    Some(upper) => Some(LeoItem {
+                        set: set_id,
+                        symbol,
+                        top: upper.top,
+                        chain: Some(self.forest.leo_link(item.dot, item.node, upper.chain)),
+                    }),
+                    // Other derivations of symbols in the chain complete the topmost item
+                    // on their own, so its node may be ambiguous. Nodes of internal rules,
+                    // such as those left by binarization before a nullable tail, can't be.
+                    None if self.grammar.external_origin(item.dot).is_none() => None,
+                    None => Some(LeoItem {
+                        set: set_id,
+                        symbol,
+                        top: start as u32,
+                        chain: None,
+                    }),
+                };
+                self.leo.extend(leo_item);
+            }
+            start = end;
+        }
+    }
+
+    /// Checks whether a completion of the given symbol would complete any predicted item.
+    fn predicts_completion_of(&self, set_id: Origin, sym: Symbol) -> bool {
+        let predicted = &self.predicted[set_id as usize];
+        let mut transitions = self
+            .grammar
+            .unary_completions(sym)
+            .iter()
+            .chain(self.grammar.binary_completions(sym));
+        transitions.any(|trans| predicted[trans.symbol.usize()])
+    }
+
+    fn leo_item(&self, set_id: Origin, sym: Symbol) -> Option<LeoItem<F::NodeRef>> {
+        self.leo
+            .binary_search_by(|leo| (leo.set, leo.symbol).cmp(&(set_id, sym)))
+            .ok()
+            .map(|idx| self.leo[idx])
+    }
+
     /// Complete items.
     pub fn complete(&mut self, set_id: Origin, sym: Symbol, rhs_link: F::NodeRef) {
         debug_assert!(sym != self.grammar.eof());
@@ -268,9 +496,26 @@ where
 
     /// Complete medial items in a given Earley set.
     fn complete_medial_items(&mut self, set_id: Origin, sym: Symbol, rhs_link: F::NodeRef) {
+        if let Some(leo_item) = self.leo_item(set_id, sym) {
+            // Complete the topmost item of the chain at once.
+            let dot = self.medial[leo_item.top as usize].dot;
+            if !self.lookahead_hint.is_empty() && !self.hint_can_follow(self.grammar.get_lhs(dot)) {
+                self.record_lookahead_rejection();
+                return;
+            }
+            let node = match leo_item.chain {
+                Some(chain) => self.forest.leo(chain, rhs_link),
+                None => rhs_link,
+            };
+            self.heap_push_linked(CompletedItemLinked {
+                idx: leo_item.top,
+                node: Some(node),
+            });
+            return;
+        }
         // Iterate through medial items to complete them.
         let set_range = self.medial_item_set_range(set_id, sym);
-        if let Some(hint) = self.lookahead_hint {
+        if !self.lookahead_hint.is_empty() {
             for idx in set_range {
                 // New completed item.
                 // from A ::= B • C
@@ -278,7 +523,8 @@ where
                 //
                 // We might link to medial items by index, here.
                 let dot = self.medial[idx].dot;
-                if !self.grammar.can_follow(self.grammar.get_lhs(dot), hint) {
+                if !self.hint_can_follow(self.grammar.get_lhs(dot)) {
+                    self.record_lookahead_rejection();
                     continue;
                 }
                 self.heap_push_linked(CompletedItemLinked {
@@ -301,6 +547,12 @@ where
         }
     }
 
+    fn record_lookahead_rejection(&mut self) {
+        if let Some(ref mut stats) = self.stats {
+            stats.lookahead_rejections += 1;
+        }
+    }
+
     fn medial_item_set_range(&mut self, set_id: Origin, sym: Symbol) -> Range<usize> {
         // Huh, can we reduce complexity here?
         let outer_start = self.indices[set_id as usize];
@@ -350,13 +602,11 @@ where
                 // ---
                 // We could push to `medial` as well and link from `complete` to `medial`.
 
-                if let Some(hint) = self.lookahead_hint {
-                    if !self
-                        .grammar
-                        .can_follow(self.grammar.get_lhs(trans.dot), hint)
-                    {
-                        continue;
-                    }
+                if !self.lookahead_hint.is_empty()
+                    && !self.hint_can_follow(self.grammar.get_lhs(trans.dot))
+                {
+                    self.record_lookahead_rejection();
+                    continue;
                 }
                 self.heap_push(CompletedItem {
                     origin: set_id,
@@ -372,11 +622,11 @@ where
     fn complete_binary_predictions(&mut self, set_id: Origin, sym: Symbol, rhs_link: F::NodeRef) {
         for trans in self.grammar.binary_completions(sym) {
             if self.predicted[set_id as usize].get(trans.symbol.usize()) {
-                if let Some(hint) = self.lookahead_hint {
-                    if !self
-                        .grammar
-                        .first(self.grammar.get_rhs1(trans.dot).unwrap(), hint)
-                    {
+                if !self.lookahead_hint.is_empty() {
+                    let rhs1 = self.grammar.get_rhs1(trans.dot).unwrap();
+                    let first = |&hint| self.grammar.first(rhs1, hint);
+                    if !self.lookahead_hint.iter().any(first) {
+                        self.record_lookahead_rejection();
                         continue;
                     }
                 }
@@ -404,6 +654,9 @@ where
         // Remove items.
         self.medial.clear();
         self.complete.clear();
+        self.pending.clear();
+        self.leo.clear();
+        self.checkpoint_depth = 0;
         self.initialize();
     }
 
@@ -468,7 +721,7 @@ where
             // Include all items in the completion.
             completion.complete_entire_sum();
         }
-        self.lookahead_hint = None;
+        self.lookahead_hint.clear();
     }
 
     /// Allows iteration through groups of completions that have unique symbol and origin.
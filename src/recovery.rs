@@ -0,0 +1,162 @@
+use cfg::Symbol;
+
+use forest::Forest;
+use item::Origin;
+use recognizer::Recognizer;
+
+/// Configuration of the error recovery mode.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ErrorRecovery {
+    /// The external terminal that stands for erroneous input. Rules of the grammar that
+    /// contain this terminal are used for repairs.
+    pub error_symbol: Symbol,
+    /// The number of tokens that must be accepted after a repair.
+    pub token_matches: usize,
+}
+
+/// A repair made by error recovery.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Repair {
+    /// The number of upcoming input tokens covered by the error token. When zero, the error
+    /// token is inserted before the rejected token.
+    pub skipped: usize,
+}
+
+impl<'g, F> Recognizer<'g, F>
+where
+    F: Forest,
+{
+    /// Enables or disables the error recovery mode.
+    pub fn set_error_recovery(&mut self, error_recovery: Option<ErrorRecovery>) {
+        self.error_recovery = error_recovery;
+    }
+
+    /// Repairs the input at the current location. The `tokens` are the upcoming input,
+    /// starting with the rejected token.
+    ///
+    /// The error token starts at the nearest retained Earley set that expects it, which may
+    /// come before the current location. In that case, the error token also covers tokens
+    /// that were read after that set. The search looks for the minimal number of tokens to
+    /// skip, such that after an error token that covers them, the next `token_matches` tokens
+    /// are accepted. Farther sets are tried when no repair is found. The error token is
+    /// scanned as an error leaf and the parse is advanced, so that the caller can continue
+    /// with the first token that was not skipped.
+    ///
+    /// Returns `None` when the recovery mode is disabled, when no retained set expects
+    /// the error symbol, or when no repair is found.
+    pub fn recover(&mut self, tokens: &[Symbol]) -> Option<Repair>
+    where
+        F::LeafValue: Default,
+    {
+        let recovery = self.error_recovery?;
+        let error_symbol = recovery.error_symbol;
+        let internal = self.grammar.to_internal(error_symbol)?;
+        // Repairs are only made at the beginning of an Earley set.
+        if !self.complete.is_empty() {
+            return None;
+        }
+        let origins: Vec<Origin> = (0..=self.earleme)
+            .rev()
+            .filter(|&set_id| self.predicted[set_id].get(internal.usize()))
+            .map(|set_id| set_id as Origin)
+            .collect();
+        for origin in origins {
+            let found = (0..=tokens.len())
+                .find(|&skipped| self.try_repair(origin, recovery, &tokens[skipped..]));
+            if let Some(skipped) = found {
+                self.complete_error(origin, skipped);
+                self.end_earleme();
+                return Some(Repair { skipped });
+            }
+        }
+        None
+    }
+
+    /// Checks whether an error token that starts at the given set is followed by accepted
+    /// `upcoming` tokens. Leaves the recognizer intact.
+    fn try_repair(&mut self, origin: Origin, recovery: ErrorRecovery, upcoming: &[Symbol]) -> bool
+    where
+        F::LeafValue: Default,
+    {
+        let checkpoint = self.checkpoint();
+        self.complete_error(origin, 0);
+        let matches = recovery.token_matches.min(upcoming.len());
+        let mut accepted = self.end_earleme();
+        for &token in &upcoming[..matches] {
+            if !accepted || !self.accepts(token) {
+                accepted = false;
+                break;
+            }
+            self.scan(token, F::LeafValue::default());
+            accepted = self.end_earleme();
+        }
+        // When the input ends early, a repair must lead to a finished parse.
+        let repaired = accepted && (matches == recovery.token_matches || self.is_finished());
+        self.rollback(checkpoint);
+        repaired
+    }
+
+    fn complete_error(&mut self, origin: Origin, skipped: usize)
+    where
+        F::LeafValue: Default,
+    {
+        let error_symbol = self.error_recovery.unwrap().error_symbol;
+        let internal = self.grammar.to_internal(error_symbol).unwrap();
+        let earleme = self.earleme() as Origin;
+        let node = self.forest.error(error_symbol, earleme + 1, skipped as u32);
+        self.complete(origin, internal, node);
+    }
+
+    /// Returns the expected terminals that, if injected at the current location, are followed
+    /// by an accepted `next` token.
+    pub fn virtual_candidates(&mut self, next: Symbol) -> Vec<Symbol>
+    where
+        F::LeafValue: Default,
+    {
+        if !self.complete.is_empty() {
+            return vec![];
+        }
+        let expected: Vec<Symbol> = self.expected_external_terminals().collect();
+        expected
+            .into_iter()
+            .filter(|&symbol| {
+                let checkpoint = self.checkpoint();
+                let accepted = self.inject_virtual(symbol, F::LeafValue::default(), next);
+                self.rollback(checkpoint);
+                accepted
+            })
+            .collect()
+    }
+
+    /// Injects a virtual token at the current location and advances the parse, but only if
+    /// the `next` token is accepted after it. Otherwise, leaves the recognizer intact.
+    ///
+    /// The token is added to the forest as a virtual leaf, so that evaluators can tell it
+    /// apart from input tokens. Returns whether the token was injected.
+    pub fn scan_virtual(&mut self, symbol: Symbol, value: F::LeafValue, next: Symbol) -> bool {
+        // Tokens are only injected at the beginning of an Earley set.
+        if !self.complete.is_empty() {
+            return false;
+        }
+        let checkpoint = self.checkpoint();
+        if self.inject_virtual(symbol, value, next) {
+            self.commit(checkpoint);
+            true
+        } else {
+            self.rollback(checkpoint);
+            false
+        }
+    }
+
+    // Scans a virtual token and advances the parse. Returns whether `next` is accepted after it.
+    fn inject_virtual(&mut self, symbol: Symbol, value: F::LeafValue, next: Symbol) -> bool {
+        if !self.accepts(symbol) {
+            return false;
+        }
+        let earleme = self.earleme() as Origin;
+        let internal = self.grammar.to_internal(symbol).unwrap();
+        let node = self.forest.virtual_leaf(symbol, earleme + 1, value);
+        self.complete(earleme, internal, node);
+        self.end_earleme() && self.accepts(next)
+    }
+}
@@ -0,0 +1,414 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+
+use bit_matrix::BitMatrix;
+use cfg::Symbol;
+
+use error::SnapshotError;
+use forest::bocage::node::CompactNode;
+use forest::node_handle::NodeHandle;
+use forest::{Bocage, CompactBocage, Forest, LeafPayload, NullForest};
+use grammar::InternalGrammar;
+use item::{CompletedItemLinked, Item, LeoItem, PendingToken};
+use recognizer::Recognizer;
+
+const MAGIC: &[u8; 4] = b"GRLY";
+const VERSION: u32 = 1;
+
+/// A forest that can be saved in a snapshot along with the recognizer.
+///
+/// Snapshots hold the graph of a `NullForest` or a `Bocage` whose token values are kept
+/// in leaf nodes, such as `u32`. Token values that are kept in the bocage's side table
+/// aren't saved, and neither is the graph of a `CompactBocage`. For these forests, an
+/// `UnsupportedForest` error is returned.
+pub trait SnapshotForest: Forest {
+    /// Writes the forest's graph.
+    fn write_graph(&self, writer: &mut SnapshotWriter) -> Result<(), SnapshotError>;
+
+    /// Replaces the forest's graph with one that is read from a snapshot.
+    fn read_graph(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError>;
+
+    /// Writes a reference to a node.
+    fn write_node(&self, node: Self::NodeRef, writer: &mut SnapshotWriter);
+
+    /// Reads a reference to a node of the graph.
+    fn read_node(&self, reader: &mut SnapshotReader) -> Result<Self::NodeRef, SnapshotError>;
+}
+
+/// Writes a snapshot in little-endian byte order.
+pub struct SnapshotWriter {
+    bytes: Vec<u8>,
+}
+
+/// Reads a snapshot in little-endian byte order.
+pub struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl SnapshotWriter {
+    pub fn write_u8(&mut self, n: u8) {
+        self.bytes.push(n);
+    }
+
+    pub fn write_u32(&mut self, n: u32) {
+        self.bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, n: u64) {
+        self.bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::Malformed);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        self.read_bytes(1).map(|bytes| bytes[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.read_bytes(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads a length or an index that must be smaller than `bound`.
+    pub fn read_usize_below(&mut self, bound: usize) -> Result<usize, SnapshotError> {
+        let n = self.read_u64()?;
+        if n < bound as u64 {
+            Ok(n as usize)
+        } else {
+            Err(SnapshotError::Malformed)
+        }
+    }
+
+    /// Reads a length of a sequence whose elements take at least `elem_size` bytes.
+    pub fn read_len(&mut self, elem_size: usize) -> Result<usize, SnapshotError> {
+        let bound = self.bytes.len() / elem_size + 1;
+        self.read_usize_below(bound)
+    }
+}
+
+impl<'g, F> Recognizer<'g, F>
+where
+    F: SnapshotForest,
+{
+    /// Saves the chart and the forest's graph to a byte buffer.
+    pub fn snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut writer = SnapshotWriter { bytes: vec![] };
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.write_u32(VERSION);
+        writer.write_u64(self.grammar.fingerprint());
+        // The graph comes first, so that nodes in items can be validated when reading.
+        self.forest.write_graph(&mut writer)?;
+        writer.write_usize(self.earleme);
+        writer.write_usize(self.current_medial_start);
+        writer.write_usize(self.indices.len());
+        for &index in &self.indices {
+            writer.write_usize(index);
+        }
+        // Rows after the current location are not used.
+        for row in 0..=self.earleme {
+            for &block in self.predicted[row].iter() {
+                writer.write_u32(block);
+            }
+        }
+        writer.write_usize(self.medial.len());
+        for item in &self.medial {
+            writer.write_u32(item.origin);
+            writer.write_u32(item.dot);
+            self.forest.write_node(item.node, &mut writer);
+        }
+        writer.write_usize(self.complete.len());
+        for item in &self.complete {
+            writer.write_u32(item.idx);
+            match item.node {
+                Some(node) => {
+                    writer.write_u8(1);
+                    self.forest.write_node(node, &mut writer);
+                }
+                None => writer.write_u8(0),
+            }
+        }
+        let pending_tokens = self
+            .pending
+            .iter()
+            .flat_map(|(&end, tokens)| tokens.iter().map(move |token| (end, token)));
+        writer.write_usize(pending_tokens.clone().count());
+        for (end, token) in pending_tokens {
+            writer.write_usize(end);
+            writer.write_u32(token.origin);
+            writer.write_u32(token.symbol.usize() as u32);
+            self.forest.write_node(token.node, &mut writer);
+        }
+        writer.write_usize(self.lookahead_hint.len());
+        for &hint in &self.lookahead_hint {
+            // The end of input is written as `!0`.
+            writer.write_u32(hint.map_or(!0, |symbol| symbol.usize() as u32));
+        }
+        writer.write_u8(self.leo_enabled as u8);
+        writer.write_usize(self.leo.len());
+        for leo in &self.leo {
+            writer.write_u32(leo.set);
+            writer.write_u32(leo.symbol.usize() as u32);
+            writer.write_u32(leo.top);
+            match leo.chain {
+                Some(node) => {
+                    writer.write_u8(1);
+                    self.forest.write_node(node, &mut writer);
+                }
+                None => writer.write_u8(0),
+            }
+        }
+        Ok(writer.bytes)
+    }
+
+    /// Rebuilds a recognizer from a snapshot taken with the same grammar and a forest of
+    /// the same type. The graph of the given forest is replaced with the saved graph.
+    pub fn restore(
+        grammar: &'g InternalGrammar,
+        forest: F,
+        bytes: &[u8],
+    ) -> Result<Self, SnapshotError> {
+        let mut reader = SnapshotReader { bytes };
+        if reader.read_bytes(MAGIC.len())? != MAGIC || reader.read_u32()? != VERSION {
+            return Err(SnapshotError::Malformed);
+        }
+        if reader.read_u64()? != grammar.fingerprint() {
+            return Err(SnapshotError::GrammarMismatch);
+        }
+        let mut recognizer = Recognizer::empty(grammar, forest);
+        recognizer.forest.read_graph(&mut reader)?;
+        // Read the chart's indices.
+        let earleme = reader.read_len(8)?;
+        let current_medial_start = reader.read_len(1)?;
+        let indices_len = reader.read_len(8)?;
+        if indices_len != earleme + 2 {
+            return Err(SnapshotError::Malformed);
+        }
+        let mut indices = Vec::with_capacity(indices_len);
+        for _ in 0..indices_len {
+            let index = reader.read_len(1)?;
+            if indices.last().is_some_and(|&last| last > index) {
+                return Err(SnapshotError::Malformed);
+            }
+            indices.push(index);
+        }
+        if indices.last() != Some(&current_medial_start) {
+            return Err(SnapshotError::Malformed);
+        }
+        // Read predicted symbols.
+        let mut predicted = BitMatrix::new(earleme + 1, grammar.num_syms());
+        for row in 0..=earleme {
+            for block in predicted[row].iter_mut() {
+                *block = reader.read_u32()?;
+            }
+        }
+        let medial_len = reader.read_len(8)?;
+        if medial_len < current_medial_start {
+            return Err(SnapshotError::Malformed);
+        }
+        // Read items.
+        let mut medial = Vec::with_capacity(medial_len);
+        for _ in 0..medial_len {
+            let origin = reader.read_u32()?;
+            let dot = reader.read_u32()?;
+            let node = recognizer.forest.read_node(&mut reader)?;
+            if origin as usize > earleme || dot as usize >= grammar.num_rules() {
+                return Err(SnapshotError::Malformed);
+            }
+            medial.push(Item { origin, dot, node });
+        }
+        let complete_len = reader.read_len(5)?;
+        let mut complete = Vec::with_capacity(complete_len);
+        for _ in 0..complete_len {
+            let idx = reader.read_u32()?;
+            let node = match reader.read_u8()? {
+                0 => None,
+                1 => Some(recognizer.forest.read_node(&mut reader)?),
+                _ => return Err(SnapshotError::Malformed),
+            };
+            if idx as usize >= medial_len {
+                return Err(SnapshotError::Malformed);
+            }
+            complete.push(CompletedItemLinked { idx, node });
+        }
+        let pending_len = reader.read_len(16)?;
+        let mut pending: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for _ in 0..pending_len {
+            let end = reader.read_len(1)?;
+            let origin = reader.read_u32()?;
+            let symbol = reader.read_u32()?;
+            let node = recognizer.forest.read_node(&mut reader)?;
+            if end <= earleme || origin as usize > earleme || symbol as usize >= grammar.num_syms()
+            {
+                return Err(SnapshotError::Malformed);
+            }
+            let token = PendingToken {
+                origin,
+                symbol: Symbol::from(symbol),
+                node,
+            };
+            pending.entry(end).or_default().push(token);
+        }
+        let lookahead_len = reader.read_len(4)?;
+        let mut lookahead_hint = Vec::with_capacity(lookahead_len);
+        for _ in 0..lookahead_len {
+            let hint = match reader.read_u32()? {
+                0xFFFF_FFFF => None,
+                symbol if (symbol as usize) < grammar.num_syms() => Some(Symbol::from(symbol)),
+                _ => return Err(SnapshotError::Malformed),
+            };
+            lookahead_hint.push(hint);
+        }
+        let leo_enabled = match reader.read_u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(SnapshotError::Malformed),
+        };
+        let leo_len = reader.read_len(13)?;
+        let mut leo = Vec::with_capacity(leo_len);
+        for _ in 0..leo_len {
+            let set = reader.read_u32()?;
+            let symbol = reader.read_u32()?;
+            let top = reader.read_u32()?;
+            let chain = match reader.read_u8()? {
+                0 => None,
+                1 => Some(recognizer.forest.read_node(&mut reader)?),
+                _ => return Err(SnapshotError::Malformed),
+            };
+            if set as usize > earleme
+                || symbol as usize >= grammar.num_syms()
+                || top as usize >= current_medial_start
+                || leo.last().is_some_and(|last: &LeoItem<_>| {
+                    (last.set, last.symbol.usize()) >= (set, symbol as usize)
+                })
+            {
+                return Err(SnapshotError::Malformed);
+            }
+            let leo_item = LeoItem {
+                set,
+                symbol: Symbol::from(symbol),
+                top,
+                chain,
+            };
+            leo.push(leo_item);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::Malformed);
+        }
+        recognizer.earleme = earleme;
+        recognizer.current_medial_start = current_medial_start;
+        recognizer.indices = indices;
+        recognizer.predicted = predicted;
+        recognizer.medial = medial;
+        recognizer.complete = complete;
+        recognizer.pending = pending;
+        recognizer.lookahead_hint = lookahead_hint;
+        recognizer.leo = leo;
+        recognizer.leo_enabled = leo_enabled;
+        Ok(recognizer)
+    }
+}
+
+impl SnapshotForest for NullForest {
+    fn write_graph(&self, _writer: &mut SnapshotWriter) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+
+    fn read_graph(&mut self, _reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+
+    fn write_node(&self, _node: (), _writer: &mut SnapshotWriter) {}
+
+    fn read_node(&self, _reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
+
+impl<G, V> SnapshotForest for Bocage<G, V>
+where
+    V: LeafPayload,
+{
+    fn write_graph(&self, writer: &mut SnapshotWriter) -> Result<(), SnapshotError> {
+        // Values in the side table can't be written.
+        if !self.leaves.is_empty() {
+            return Err(SnapshotError::UnsupportedForest);
+        }
+        writer.write_usize(self.graph.len());
+        for node in &self.graph {
+            for &word in &node.to_words() {
+                writer.write_u32(word);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_graph(&mut self, reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let len = reader.read_len(12)?;
+        self.graph.clear();
+        for _ in 0..len {
+            let words = [reader.read_u32()?, reader.read_u32()?, reader.read_u32()?];
+            self.graph.push(CompactNode::from_words(words));
+        }
+        self.gc.liveness.clear();
+        self.summand_count = 0;
+        self.leaves.clear();
+        // Handles are used without bounds checks.
+        if self.check_graph() {
+            Ok(())
+        } else {
+            Err(SnapshotError::Malformed)
+        }
+    }
+
+    fn write_node(&self, node: NodeHandle, writer: &mut SnapshotWriter) {
+        writer.write_u32(node.0);
+    }
+
+    fn read_node(&self, reader: &mut SnapshotReader) -> Result<NodeHandle, SnapshotError> {
+        let handle = reader.read_u32()?;
+        if (handle as usize) < self.graph.len() {
+            Ok(NodeHandle(handle))
+        } else {
+            Err(SnapshotError::Malformed)
+        }
+    }
+}
+
+impl<G> SnapshotForest for CompactBocage<G>
+where
+    G: Borrow<InternalGrammar>,
+{
+    fn write_graph(&self, _writer: &mut SnapshotWriter) -> Result<(), SnapshotError> {
+        Err(SnapshotError::UnsupportedForest)
+    }
+
+    fn read_graph(&mut self, _reader: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        Err(SnapshotError::UnsupportedForest)
+    }
+
+    fn write_node(&self, _node: NodeHandle, _writer: &mut SnapshotWriter) {}
+
+    fn read_node(&self, _reader: &mut SnapshotReader) -> Result<NodeHandle, SnapshotError> {
+        Err(SnapshotError::UnsupportedForest)
+    }
+}
@@ -0,0 +1,93 @@
+//! Statistics of the recognizer's work, which help with tuning grammars.
+
+use std::fmt;
+
+use forest::Forest;
+use recognizer::Recognizer;
+
+/// Counts of the work done by the recognizer since statistics were enabled. Work that is
+/// undone by a rollback, including trials of error recovery, is not counted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParseStats {
+    /// The number of items created in each Earley set, in the order in which the parse
+    /// advanced past them.
+    pub items_per_set: Vec<usize>,
+    /// The number of completed items removed from the chart because nothing follows
+    /// their dot.
+    pub unary_items_removed: usize,
+    /// The number of Earley sets removed because no item refers to them.
+    pub sets_dropped: usize,
+    /// The number of completed items pushed onto the heap.
+    pub heap_pushes: usize,
+    /// The number of completed items popped from the heap.
+    pub heap_pops: usize,
+    /// The number of completions skipped because the lookahead hint can't follow them.
+    pub lookahead_rejections: usize,
+    /// The number of nodes added to the forest's graph.
+    pub forest_nodes: usize,
+    // The length of the forest's graph when the parse last advanced.
+    graph_len: usize,
+}
+
+impl ParseStats {
+    fn new(graph_len: usize) -> Self {
+        ParseStats {
+            graph_len,
+            ..ParseStats::default()
+        }
+    }
+
+    /// Returns the total number of items created.
+    pub fn items(&self) -> usize {
+        self.items_per_set.iter().sum()
+    }
+
+    // Counts nodes added since the last call. Rollbacks may shrink the graph.
+    pub(super) fn record_graph_len(&mut self, graph_len: usize) {
+        self.forest_nodes += graph_len.saturating_sub(self.graph_len);
+        self.graph_len = graph_len;
+    }
+}
+
+impl fmt::Display for ParseStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "sets: {}, dropped: {}",
+            self.items_per_set.len(),
+            self.sets_dropped
+        )?;
+        writeln!(
+            f,
+            "items: {}, unary removed: {}",
+            self.items(),
+            self.unary_items_removed
+        )?;
+        writeln!(
+            f,
+            "heap pushes: {}, pops: {}",
+            self.heap_pushes, self.heap_pops
+        )?;
+        writeln!(f, "lookahead rejections: {}", self.lookahead_rejections)?;
+        writeln!(f, "forest nodes: {}", self.forest_nodes)
+    }
+}
+
+impl<'g, F> Recognizer<'g, F>
+where
+    F: Forest,
+{
+    /// Enables or disables the collection of statistics. Enabling starts from zero.
+    pub fn set_stats(&mut self, enabled: bool) {
+        self.stats = if enabled {
+            Some(ParseStats::new(self.forest.graph_len()))
+        } else {
+            None
+        };
+    }
+
+    /// Returns statistics collected so far, or `None` when the collection is disabled.
+    pub fn stats(&self) -> Option<&ParseStats> {
+        self.stats.as_ref()
+    }
+}
@@ -4,7 +4,9 @@ use std::mem;
 
 use cfg::Symbol;
 
-use gearley::forest::bocage::traverse::{LeafHandle, NullingHandle, SumHandle, Traverse};
+use gearley::forest::bocage::traverse::{
+    ErrorHandle, LeafHandle, NullingHandle, SumHandle, Traverse, VirtualHandle,
+};
 use gearley::forest::node_handle::NodeHandle;
 
 use gearley::grammar::InternalGrammar;
@@ -60,7 +62,7 @@ where
                 &mut NullingHandle => {
                     (self.null)(item.symbol, &mut self.values);
                 }
-                &mut LeafHandle(_) => {
+                &mut LeafHandle(_) | &mut ErrorHandle(_) | &mut VirtualHandle(_) => {
                     let v = (self.leaf)(item.symbol);
                     self.values.push(v);
                 }
@@ -0,0 +1,81 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::bocage::traverse::LeafHandle;
+use gearley::forest::{Bocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+fn grammar() -> (Grammar, [Symbol; 3]) {
+    let mut external = Grammar::new();
+    let (stmt, kw_if, ident, eq) = external.sym();
+    external
+        .rule(stmt)
+        .rhs([kw_if, ident])
+        .rhs([ident, eq, ident]);
+    external.set_start(stmt);
+    (external, [kw_if, ident, eq])
+}
+
+#[test]
+fn test_keyword_as_identifier() {
+    let _ = env_logger::try_init();
+    let (external, [kw_if, ident, eq]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    // `if = x`
+    assert_eq!(rec.scan_alternatives(vec![(kw_if, 0), (ident, 0)]), 2);
+    assert!(rec.end_earleme());
+    assert_eq!(rec.scan_alternatives(vec![(eq, 1)]), 1);
+    assert!(rec.end_earleme());
+    assert_eq!(rec.scan_alternatives(vec![(kw_if, 2), (ident, 2)]), 1);
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut leaves = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let LeafHandle(value) = item.item {
+            leaves.push((value, item.symbol));
+        }
+    }
+    leaves.sort();
+    assert_eq!(leaves, vec![(0, ident), (1, eq), (2, ident)]);
+}
+
+#[test]
+fn test_lookahead_hints() {
+    let _ = env_logger::try_init();
+    let (external, [kw_if, ident, eq]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.lookahead_hints(vec![Some(eq), Some(ident)]);
+    assert_eq!(rec.scan_alternatives(vec![(kw_if, ()), (ident, ())]), 2);
+    assert!(rec.end_earleme());
+    assert!(rec.accepts(eq));
+    assert!(rec.accepts(ident));
+
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    // The keyword can't be followed by `=`.
+    rec.lookahead_hints(vec![Some(eq)]);
+    assert_eq!(rec.scan_alternatives(vec![(kw_if, ()), (ident, ())]), 2);
+    assert!(rec.end_earleme());
+    assert!(rec.accepts(eq));
+    assert!(!rec.accepts(ident));
+
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    // A symbol from outside of the grammar is ignored.
+    let unknown = Symbol::from(100usize);
+    rec.lookahead_hints(vec![Some(eq), Some(unknown)]);
+    assert_eq!(rec.scan_alternatives(vec![(kw_if, ()), (ident, ())]), 2);
+    assert!(rec.end_earleme());
+    assert!(rec.accepts(eq));
+    assert!(!rec.accepts(ident));
+}
@@ -0,0 +1,166 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::eval::{BuildError, Builder};
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::compact_bocage::order::NullOrder as CompactNullOrder;
+use gearley::forest::{Bocage, CompactBocage};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Num(u32),
+    Plus,
+    Add(Box<Expr>, Box<Expr>),
+}
+
+fn add(left: u32, right: u32) -> Expr {
+    Expr::Add(Box::new(Expr::Num(left)), Box::new(Expr::Num(right)))
+}
+
+fn grammar(ambiguous: bool) -> (Grammar, Symbol, Symbol) {
+    let mut external = Grammar::new();
+    let (expr, plus, num) = external.sym();
+    if ambiguous {
+        external.rule(expr).rhs([expr, plus, expr]).rhs([num]);
+    } else {
+        external.rule(expr).rhs([expr, plus, num]).rhs([num]);
+    }
+    external.set_start(expr);
+    (external, plus, num)
+}
+
+fn builder<'a>(plus: Symbol, num: Symbol) -> Builder<'a, Expr> {
+    let mut builder = Builder::new();
+    builder
+        .rule(0, |args: &[&Expr]| {
+            Expr::Add(Box::new(args[0].clone()), Box::new(args[2].clone()))
+        })
+        .rule(1, |args: &[&Expr]| args[0].clone())
+        .terminal(plus, |_| Expr::Plus)
+        .terminal(num, Expr::Num);
+    builder
+}
+
+fn tokens(plus: Symbol, num: Symbol) -> Vec<(Symbol, u32)> {
+    vec![(num, 1), (plus, 0), (num, 2), (plus, 0), (num, 3)]
+}
+
+macro_rules! build {
+    ($Bocage:ident, $Order:ident, $build:ident, $cfg:expr, $tokens:expr, $builder:expr) => {{
+        let mut rec = Recognizer::new($cfg, $Bocage::new($cfg));
+        for (symbol, value) in $tokens {
+            rec.scan(symbol, value);
+            assert!(rec.end_earleme());
+        }
+        let root = rec.finished_node().unwrap();
+        rec.forest.mark_alive(root, $Order::new());
+        let mut traverse = rec.forest.traverse();
+        $builder.$build(&mut traverse, root)
+    }};
+}
+
+#[test]
+fn test_build() {
+    let _ = env_logger::try_init();
+    let (external, plus, num) = grammar(false);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut builder = builder(plus, num);
+    let tree = build!(Bocage, NullOrder, build, &cfg, tokens(plus, num), builder);
+    assert_eq!(
+        tree,
+        Ok(Expr::Add(Box::new(add(1, 2)), Box::new(Expr::Num(3))))
+    );
+}
+
+#[test]
+fn test_build_compact() {
+    let _ = env_logger::try_init();
+    let (external, plus, num) = grammar(false);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut builder = builder(plus, num);
+    let tree = build!(
+        CompactBocage,
+        CompactNullOrder,
+        build_compact,
+        &cfg,
+        tokens(plus, num),
+        builder
+    );
+    assert_eq!(
+        tree,
+        Ok(Expr::Add(Box::new(add(1, 2)), Box::new(Expr::Num(3))))
+    );
+}
+
+#[test]
+fn test_missing_constructor() {
+    let _ = env_logger::try_init();
+    let (external, plus, num) = grammar(false);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut terminals_only = Builder::new();
+    terminals_only
+        .terminal(num, Expr::Num)
+        .terminal(plus, |_| Expr::Plus);
+    let tree = build!(
+        Bocage,
+        NullOrder,
+        build,
+        &cfg,
+        tokens(plus, num),
+        terminals_only
+    );
+    assert_eq!(tree, Err(BuildError::MissingRule(1)));
+    assert_eq!(tree.unwrap_err().to_string(), "no constructor for rule 1");
+    let mut without_plus = Builder::new();
+    without_plus
+        .rule(0, |_| Expr::Plus)
+        .rule(1, |_| Expr::Plus)
+        .terminal(num, Expr::Num);
+    let tree = build!(
+        Bocage,
+        NullOrder,
+        build,
+        &cfg,
+        tokens(plus, num),
+        without_plus
+    );
+    assert_eq!(tree, Err(BuildError::MissingTerminal(plus)));
+}
+
+#[test]
+fn test_ambiguous() {
+    let _ = env_logger::try_init();
+    let (external, plus, num) = grammar(true);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut builder = builder(plus, num);
+    let tree = build!(Bocage, NullOrder, build, &cfg, tokens(plus, num), builder);
+    match tree {
+        Err(BuildError::Ambiguous(error)) => assert_eq!(error.count, 2),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_no_tree() {
+    let _ = env_logger::try_init();
+    let (external, plus, num) = grammar(false);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut builder = builder(plus, num);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (symbol, value) in tokens(plus, num) {
+        rec.scan(symbol, value);
+        assert!(rec.end_earleme());
+    }
+    let root = rec.finished_node().unwrap();
+    // The root isn't marked alive.
+    let mut traverse = rec.forest.traverse();
+    let tree = builder.build(&mut traverse, root);
+    assert_eq!(tree, Err(BuildError::NoTree));
+    assert_eq!(tree.unwrap_err().to_string(), "the root has no value");
+}
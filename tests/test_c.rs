@@ -17,7 +17,7 @@ use gearley::memory_use::MemoryUse;
 
 use helpers::Parse;
 
-const _SYM_NAMES: &'static [&'static str] = &[
+const SYM_NAMES: &'static [&'static str] = &[
     "term", "identifier", "signed", "const_", "inline", "auto", "break_", "case", "char_", "continue_", "default",
     "do_", "double", "else_", "enum_", "extern_", "float", "for_", "goto", "if_", "int", "long", "register", "return_",
     "short", "sizeof_", "static_", "struct_", "switch", "typedef", "union", "unsigned", "void", "volatile", "while_",
@@ -464,7 +464,7 @@ fn test_parse_c() {
         // tok.map(|t| (t.usize() as u32, start, end))
         tok.map(|t| t.usize() as u32)
     }).collect();
-    let cfg = InternalGrammar::from_grammar(&external);
+    let cfg = InternalGrammar::from_grammar_with_names(&external, SYM_NAMES);
     let bocage = Bocage::new(&cfg);
     let mut rec: Recognizer<Bocage<&'_ InternalGrammar>> = Recognizer::new_with_limit(&cfg, 2_00_000);
     rec.forest = bocage;
@@ -0,0 +1,88 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::forest::{Bocage, Forest, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_rollback() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b, c) = external.sym();
+    external.rule(start).rhs([a, b]).rhs([a, c]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(a, 0);
+    assert!(rec.end_earleme());
+    let graph_len = rec.forest.graph_len();
+    let checkpoint = rec.checkpoint();
+    assert_eq!(checkpoint.earleme(), 1);
+    rec.scan(b, 1);
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+    rec.rollback(checkpoint);
+    assert_eq!(rec.earleme(), 1);
+    assert!(!rec.is_finished());
+    assert_eq!(rec.forest.graph_len(), graph_len);
+    rec.scan(c, 1);
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
+
+#[test]
+fn test_no_compaction() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x, a, b, c, d) = external.sym();
+    external.rule(start).rhs([a, x]);
+    external.rule(x).rhs([b, c, d]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let tokens = &[a, b, c, d];
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    for &token in tokens {
+        rec.scan(token, ());
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+    // Earlier sets are removed without a checkpoint.
+    assert!(rec.earleme() < tokens.len());
+    rec.reset();
+    let checkpoint = rec.checkpoint();
+    for &token in tokens {
+        rec.scan(token, ());
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+    assert_eq!(rec.earleme(), tokens.len());
+    rec.rollback(checkpoint);
+    assert_eq!(rec.earleme(), 0);
+    for &token in tokens {
+        rec.scan(token, ());
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+}
+
+#[test]
+fn test_commit() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a) = external.sym();
+    external.rule(start).rhs([a, a]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    let checkpoint = rec.checkpoint();
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    rec.commit(checkpoint);
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
@@ -0,0 +1,41 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::forest::compact_bocage::order::NullOrder;
+use gearley::forest::{CompactBocage, Tree};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_leaf_values() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, x, x, x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, CompactBocage::new(&cfg));
+    // Values of every encoded width.
+    let values = [0, 300, 70_000, 0xFFFF_FFFE];
+    for &value in &values {
+        rec.scan(x, value);
+        assert!(rec.end_earleme());
+    }
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let trees: Vec<_> = rec.forest.traverse().trees(root).collect();
+    let children = values
+        .iter()
+        .map(|&value| Tree::Leaf { symbol: x, value })
+        .collect();
+    assert_eq!(
+        trees,
+        vec![Tree::Rule {
+            action: 0,
+            children,
+        }]
+    );
+}
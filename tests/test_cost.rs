@@ -0,0 +1,113 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::bocage::order::{Costs, MinimalCostOrder, NullOrder};
+use gearley::forest::Bocage;
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use helpers::SimpleEvaluator;
+
+fn evaluate(rec: &Recognizer<Bocage<&InternalGrammar>>) -> Vec<Vec<u32>> {
+    let mut evaluator = SimpleEvaluator::new(
+        |sym: Symbol| vec![sym.usize() as u32],
+        |rule: u32, args: &[&Vec<u32>]| {
+            let mut result = vec![rule + 100];
+            for arg in args {
+                result.extend(arg.iter().cloned());
+            }
+            result
+        },
+        |_, _: &mut Vec<Vec<u32>>| unreachable!(),
+    );
+    let mut traverse = rec.forest.traverse();
+    evaluator.traverse(&mut traverse, rec.finished_node().unwrap())
+}
+
+#[test]
+fn test_rule_costs() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b, x) = external.sym();
+    // Rules 0 to 3.
+    external.rule(start).rhs([a]).rhs([b]);
+    external.rule(a).rhs([x]);
+    external.rule(b).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(x, 0);
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+
+    let mut costs = Costs::new();
+    costs.set_rule_cost(2, 5);
+    costs.set_rule_cost(3, 1);
+    let order = MinimalCostOrder::new(&rec.forest, &costs);
+    assert_eq!(order.cost(root), 1);
+    rec.forest.mark_alive(root, order);
+    assert_eq!(evaluate(&rec), vec![vec![101, 103, x.usize() as u32]]);
+}
+
+#[test]
+fn test_token_costs() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, error, b) = external.sym();
+    external.rule(start).rhs([a, b]).rhs([a, error]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(a, 0);
+    assert!(rec.end_earleme());
+    // The lexer may report an unexpected token as an error.
+    rec.scan_alternatives(vec![(error, 1), (b, 1)]);
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+
+    let mut costs = Costs::new();
+    costs.set_token_cost(error, 10);
+    let order = MinimalCostOrder::new(&rec.forest, &costs);
+    rec.forest.mark_alive(root, order);
+    let sym = |s: Symbol| s.usize() as u32;
+    assert_eq!(evaluate(&rec), vec![vec![100, sym(a), sym(b)]]);
+}
+
+#[test]
+fn test_leo_costs() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    // Rules 0 and 1.
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut costs = Costs::new();
+    costs.set_rule_cost(0, 10);
+    costs.set_rule_cost(1, 100);
+    costs.set_token_cost(x, 1);
+    let expected = 9 * (10 + 1) + 100 + 1;
+    for &leo in &[false, true] {
+        let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+        rec.set_leo(leo);
+        for _ in 0..10 {
+            rec.scan(x, 0);
+            assert!(rec.end_earleme());
+        }
+        let root = rec.finished_node().unwrap();
+        let order = MinimalCostOrder::new(&rec.forest, &costs);
+        assert_eq!(order.cost(root), expected);
+        // Marking expands Leo chains, which are then costed as a whole.
+        rec.forest.mark_alive(root, NullOrder::new());
+        let order = MinimalCostOrder::new(&rec.forest, &costs);
+        assert_eq!(order.cost(root), expected);
+    }
+}
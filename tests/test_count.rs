@@ -0,0 +1,135 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+extern crate num;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+use num::BigUint;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::{Bocage, CompactBocage, Forest, TreeCount};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+
+fn sums(count: usize) -> Vec<u32> {
+    let mut tokens = ambiguous_arith!('1').to_vec();
+    for _ in 1..count {
+        tokens.extend(ambiguous_arith!('+' '1'));
+    }
+    tokens
+}
+
+fn finite(count: u64) -> TreeCount {
+    TreeCount::Finite(BigUint::from(count))
+}
+
+macro_rules! test_count {
+    ($Bocage:ident) => {
+        let _ = env_logger::try_init();
+        let external = ambiguous_arith::grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let count = |n: usize| {
+            let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+            for (i, &token) in sums(n).iter().enumerate() {
+                rec.scan(Symbol::from(token), i as u32);
+                assert!(rec.end_earleme());
+            }
+            rec.forest.count_trees(rec.finished_node().unwrap())
+        };
+        // Catalan numbers.
+        assert_eq!(count(1), finite(1));
+        assert_eq!(count(3), finite(2));
+        assert_eq!(count(7), finite(132));
+    };
+}
+
+#[test]
+fn test_count() {
+    test_count!(Bocage);
+}
+
+#[test]
+fn test_count_compact() {
+    test_count!(CompactBocage);
+}
+
+#[test]
+fn test_count_matches_trees() {
+    let _ = env_logger::try_init();
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (i, &token) in sums(6).iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    let root = rec.finished_node().unwrap();
+    let count = rec.forest.count_trees(root);
+    rec.forest.mark_alive(root, NullOrder::new());
+    let trees = rec.forest.traverse().trees(root).count();
+    assert_eq!(count, finite(trees as u64));
+}
+
+#[test]
+fn test_big_count() {
+    let _ = env_logger::try_init();
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (i, &token) in sums(40).iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    let count = rec.forest.count_trees(rec.finished_node().unwrap());
+    // The 39th Catalan number does not fit in 64 bits.
+    let expected = "680425371729975800390".parse::<BigUint>().unwrap();
+    assert_eq!(count, TreeCount::Finite(expected));
+}
+
+#[test]
+fn test_count_with_leo() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_leo(true);
+    for i in 0..50 {
+        rec.scan(x, i);
+        assert!(rec.end_earleme());
+    }
+    assert_eq!(
+        rec.forest.count_trees(rec.finished_node().unwrap()),
+        finite(1)
+    );
+}
+
+#[test]
+fn test_cycle() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut bocage = Bocage::new(&cfg);
+    // Recognized grammars don't lead to cycles, so two links of a Leo chain are made
+    // to point at each other.
+    let leaf = bocage.leaf(x, 0, 0);
+    let next = bocage.nulling(Symbol::from(bocage.graph_len() + 1));
+    let link = bocage.leo_link(0, leaf, Some(next));
+    bocage.leo_link(0, leaf, Some(link));
+    assert_eq!(bocage.count_trees(link), TreeCount::Infinite);
+    assert_eq!(bocage.count_trees(leaf), finite(1));
+}
@@ -0,0 +1,130 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+
+use gearley::forest::{Bocage, CompactBocage};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::Parse;
+
+macro_rules! test_ambiguous_dot {
+    ($Bocage:ident) => {
+        let _ = env_logger::try_init();
+        let tokens = ambiguous_arith!('1' '+' '2' '*' '3');
+        let external = ambiguous_arith::grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        assert!(rec.parse(tokens));
+        let mut out = vec![];
+        rec.forest
+            .write_dot(rec.finished_node().unwrap(), &mut out)
+            .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("digraph forest {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches("shape=ellipse").count(), 1);
+        // Two alternatives of the root and two nested expressions.
+        assert_eq!(dot.matches("label=\"rule 0\"").count(), 4);
+        assert_eq!(dot.matches("shape=plaintext").count(), 5);
+        assert!(dot.contains("label=\"10 = 4\""));
+    };
+}
+
+#[test]
+fn test_ambiguous_dot() {
+    test_ambiguous_dot!(Bocage);
+}
+
+#[test]
+fn test_ambiguous_dot_compact() {
+    test_ambiguous_dot!(CompactBocage);
+}
+
+macro_rules! test_transparent_dot {
+    ($Bocage:ident) => {
+        let _ = env_logger::try_init();
+        let mut external = Grammar::new();
+        let (start, a, b, c, d) = external.sym();
+        external.rule(start).rhs([a, b, c, d]);
+        external.set_start(start);
+        let cfg = InternalGrammar::from_grammar(&external);
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        assert!(rec.parse(&[1, 2, 3, 4]));
+        let root = rec.finished_node().unwrap();
+        let mut out = vec![];
+        rec.forest.write_dot(root, &mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert_eq!(dot.matches("shape=box").count(), 1);
+        assert!(!dot.contains("internal"));
+        assert_eq!(dot.matches(" -> ").count(), 4);
+        let mut out = vec![];
+        rec.forest
+            .write_dot_with_transparent(root, &mut out)
+            .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert_eq!(dot.matches("label=\"internal\"").count(), 2);
+        assert_eq!(dot.matches(" -> ").count(), 6);
+    };
+}
+
+#[test]
+fn test_transparent_dot() {
+    test_transparent_dot!(Bocage);
+}
+
+#[test]
+fn test_transparent_dot_compact() {
+    test_transparent_dot!(CompactBocage);
+}
+
+#[test]
+fn test_leo_dot() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_leo(true);
+    for i in 0..5 {
+        rec.scan(x, i);
+        assert!(rec.end_earleme());
+    }
+    let root = rec.finished_node().unwrap();
+    let mut out = vec![];
+    rec.forest.write_dot(root, &mut out).unwrap();
+    let dot = String::from_utf8(out).unwrap();
+    assert_eq!(dot.matches("label=\"rule 0\"").count(), 4);
+    assert_eq!(dot.matches("label=\"rule 1\"").count(), 1);
+    assert_eq!(dot.matches("shape=plaintext").count(), 5);
+}
+
+#[test]
+fn test_escaped_label() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(x, "say \"hi\"");
+    assert!(rec.end_earleme());
+    let mut out = vec![];
+    rec.forest
+        .write_dot(rec.finished_node().unwrap(), &mut out)
+        .unwrap();
+    let dot = String::from_utf8(out).unwrap();
+    assert!(dot.contains(r#"label="1 = \"say \\\"hi\\\"\"""#));
+}
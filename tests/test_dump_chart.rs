@@ -0,0 +1,74 @@
+extern crate cfg;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::forest::NullForest;
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_dump_chart() {
+    let mut external = Grammar::new();
+    let (start, list, a, b) = external.sym();
+    external.rule(start).rhs([list, b]);
+    external.rule(list).rhs([a, a, a]).rhs([a]);
+    external.set_start(start);
+    let mut cfg = InternalGrammar::from_grammar(&external);
+    cfg.set_symbol_name(start, "start");
+    cfg.set_symbol_name(list, "list");
+    cfg.set_symbol_name(a, "a");
+    cfg.set_symbol_name(b, "b");
+    cfg.set_rule_name(0, "pair");
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    let mut out = vec![];
+    rec.dump_chart(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let expected = "set 0:
+  pair at 0: start -> · list b
+  rule 1 at 0: list -> · a a a
+  rule 2 at 0: list -> · a
+  expected: a
+set 1:
+  pair at 1: start -> list · b, origin 0
+  rule 1 at 1: list -> a · a a, origin 0
+  expected: b a
+set 2:
+  rule 1 at 2: list -> a a · a, origin 0
+  expected: a
+";
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn test_dump_finished_chart() {
+    let mut external = Grammar::new();
+    let (start, a, b, empty) = external.sym();
+    external.rule(start).rhs([a, empty, b]);
+    external.rule(empty).rhs([]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar_with_names(&external, &["start", "a", "b", "empty"]);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    rec.scan(b, ());
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+    let mut out = vec![];
+    rec.dump_chart(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let expected = "set 0:
+  rule 0 at 0: start -> · a empty b
+  expected: a
+set 1:
+  rule 0 at 2: start -> a empty · b, origin 0
+  expected: b
+set 2:
+  <start> -> start · <eof>, origin 0
+";
+    assert_eq!(out, expected);
+}
@@ -0,0 +1,130 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::eval::{Ambiguity, AmbiguityError, Evaluation, Evaluator};
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::{Bocage, CompactBocage};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::Parse;
+
+struct Arith;
+
+impl Evaluator for Arith {
+    type Value = i32;
+
+    fn leaf(&mut self, symbol: Symbol, _value: u32) -> i32 {
+        ambiguous_arith::leaf(symbol)
+    }
+
+    fn rule(&mut self, action: u32, args: &[&i32]) -> i32 {
+        ambiguous_arith::rule(action, args)
+    }
+
+    fn nulling(&mut self, _symbol: Symbol, _values: &mut Vec<i32>) {
+        unreachable!()
+    }
+
+    fn error(&mut self, _symbol: Symbol, _skipped: u32) -> i32 {
+        unreachable!()
+    }
+}
+
+macro_rules! test_ambiguity {
+    ($Bocage:ident, $traverse:ident, $ambiguity:expr) => {{
+        let _ = env_logger::try_init();
+        let tokens = ambiguous_arith!('2' '-' '0' '*' '3' '+' '1');
+        let external = ambiguous_arith::grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        assert!(rec.parse(tokens));
+        let mut evaluation = Evaluation::new(Arith, $ambiguity);
+        let mut traverse = rec.forest.traverse();
+        evaluation.$traverse(&mut traverse, rec.finished_node().unwrap())
+    }};
+}
+
+#[test]
+fn test_all() {
+    let mut results = test_ambiguity!(Bocage, traverse, Ambiguity::All).unwrap();
+    results.sort();
+    assert_eq!(results, vec![1, 2, 3, 7, 8]);
+}
+
+#[test]
+fn test_all_compact() {
+    let mut results = test_ambiguity!(CompactBocage, traverse_compact, Ambiguity::All).unwrap();
+    results.sort();
+    assert_eq!(results, vec![1, 2, 3, 7, 8]);
+}
+
+#[test]
+fn test_first() {
+    let results = test_ambiguity!(Bocage, traverse, Ambiguity::First).unwrap();
+    assert_eq!(results.len(), 1);
+    let results = test_ambiguity!(CompactBocage, traverse_compact, Ambiguity::First).unwrap();
+    assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn test_error() {
+    let error: AmbiguityError = test_ambiguity!(Bocage, traverse, Ambiguity::Error).unwrap_err();
+    assert!(error.count > 1);
+    let error = test_ambiguity!(CompactBocage, traverse_compact, Ambiguity::Error).unwrap_err();
+    assert!(error.count > 1);
+}
+
+struct Tokens;
+
+impl Evaluator for Tokens {
+    type Value = Vec<u32>;
+
+    fn leaf(&mut self, _symbol: Symbol, value: u32) -> Vec<u32> {
+        vec![value]
+    }
+
+    fn rule(&mut self, _action: u32, args: &[&Vec<u32>]) -> Vec<u32> {
+        args.iter().flat_map(|arg| arg.iter().cloned()).collect()
+    }
+
+    fn nulling(&mut self, _symbol: Symbol, values: &mut Vec<Vec<u32>>) {
+        values.push(vec![]);
+    }
+
+    fn error(&mut self, _symbol: Symbol, skipped: u32) -> Vec<u32> {
+        vec![skipped]
+    }
+}
+
+#[test]
+fn test_leaf_values() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x, y) = external.sym();
+    external.rule(start).rhs([x, y, x]);
+    external.rule(y).rhs([]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(x, 10);
+    assert!(rec.end_earleme());
+    rec.scan(x, 20);
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut evaluation = Evaluation::new(Tokens, Ambiguity::Error);
+    let results = evaluation.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, Ok(vec![vec![10, 20]]));
+}
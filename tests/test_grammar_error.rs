@@ -0,0 +1,144 @@
+extern crate cfg;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::sequence::Separator::Trailing;
+
+use gearley::error::GrammarError;
+use gearley::forest::NullForest;
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_valid_grammar() {
+    let mut external = Grammar::new();
+    let (start, list, a, comma) = external.sym();
+    external.rule(start).rhs([list]).rhs([]);
+    external
+        .sequence(list)
+        .separator(Trailing(comma))
+        .inclusive(1, None)
+        .rhs(a);
+    external.set_start(start);
+    let cfg = InternalGrammar::try_from_grammar(&external).unwrap();
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    rec.scan(comma, ());
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
+
+#[test]
+fn test_missing_start() {
+    let mut external = Grammar::new();
+    let (start, a) = external.sym();
+    external.rule(a).rhs([start]);
+    external.set_start(start);
+    let error = InternalGrammar::try_from_grammar(&external).err();
+    assert_eq!(error, Some(GrammarError::MissingStart));
+}
+
+#[test]
+fn test_no_start() {
+    let mut external = Grammar::new();
+    let (start, a) = external.sym();
+    external.rule(start).rhs([a]);
+    let error = InternalGrammar::try_from_grammar(&external).err();
+    assert_eq!(error, Some(GrammarError::MissingStart));
+}
+
+#[test]
+fn test_unproductive() {
+    let mut external = Grammar::new();
+    let (start, a, b, c) = external.sym();
+    external.rule(start).rhs([a, b]).rhs([a, a]);
+    external.rule(b).rhs([b, c]);
+    external.set_start(start);
+    let names = &["start", "a", "b", "c"];
+    let error = InternalGrammar::try_from_grammar_with_names(&external, names)
+        .err()
+        .unwrap();
+    assert_eq!(error, GrammarError::Unproductive(vec![b]));
+    assert_eq!(error.display(names).to_string(), "unproductive symbols: b");
+    assert_eq!(error.to_string(), "unproductive symbols: 2");
+}
+
+#[test]
+fn test_inaccessible() {
+    let mut external = Grammar::new();
+    let (start, a, unused, dangling) = external.sym();
+    external.rule(start).rhs([a]);
+    external.rule(unused).rhs([a, dangling]);
+    external.rule(dangling).rhs([a]);
+    external.set_start(start);
+    let error = InternalGrammar::try_from_grammar(&external).err().unwrap();
+    assert_eq!(error, GrammarError::Inaccessible(vec![unused, dangling]));
+    let names = &["start", "a", "unused"];
+    assert_eq!(
+        error.display(names).to_string(),
+        "inaccessible symbols: unused 3"
+    );
+}
+
+#[test]
+fn test_cycle() {
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a]).rhs([b]);
+    external.rule(b).rhs([start]);
+    external.set_start(start);
+    let error = InternalGrammar::try_from_grammar(&external).err();
+    assert_eq!(error, Some(GrammarError::Cycle(vec![start, b])));
+}
+
+#[test]
+fn test_cycle_through_nullable() {
+    let mut external = Grammar::new();
+    let (start, a, b, empty) = external.sym();
+    external.rule(start).rhs([a]).rhs([b, empty]);
+    external.rule(b).rhs([start]);
+    external.rule(empty).rhs([]);
+    external.set_start(start);
+    let error = InternalGrammar::try_from_grammar(&external).err();
+    assert_eq!(error, Some(GrammarError::Cycle(vec![start, b])));
+}
+
+#[test]
+fn test_unit_self_rule() {
+    let mut external = Grammar::new();
+    let (start, a) = external.sym();
+    external.rule(start).rhs([start]).rhs([a]);
+    external.set_start(start);
+    let error = InternalGrammar::try_from_grammar(&external).err();
+    assert_eq!(error, Some(GrammarError::Cycle(vec![start])));
+}
+
+#[test]
+fn test_unit_self_rule_through_nullable() {
+    let mut external = Grammar::new();
+    let (start, a, empty) = external.sym();
+    external.rule(start).rhs([start, empty]).rhs([a]);
+    external.rule(empty).rhs([]);
+    external.set_start(start);
+    let error = InternalGrammar::try_from_grammar(&external).err();
+    assert_eq!(error, Some(GrammarError::Cycle(vec![start])));
+}
+
+#[test]
+fn test_duplicate_empty_rule() {
+    let mut external = Grammar::new();
+    let (start, a, empty) = external.sym();
+    external.rule(start).rhs([a, empty]);
+    external.rule(empty).rhs([]).rhs([]);
+    external.set_start(start);
+    let names = &["start", "a", "empty"];
+    let error = InternalGrammar::try_from_grammar_with_names(&external, names)
+        .err()
+        .unwrap();
+    assert_eq!(error, GrammarError::DuplicateEmptyRule(vec![empty]));
+    assert_eq!(
+        error.display(names).to_string(),
+        "symbols with duplicate empty rules: empty"
+    );
+}
@@ -0,0 +1,182 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::eval::{Ambiguity, Builder, Evaluation, Evaluator};
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::{Bocage, LeafPayload, Tree};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+struct Concat;
+
+impl Evaluator<String> for Concat {
+    type Value = String;
+
+    fn leaf(&mut self, _symbol: Symbol, value: String) -> String {
+        value
+    }
+
+    fn rule(&mut self, _action: u32, args: &[&String]) -> String {
+        args.iter().map(|arg| &arg[..]).collect()
+    }
+
+    fn nulling(&mut self, _symbol: Symbol, _values: &mut Vec<String>) {
+        unreachable!()
+    }
+
+    fn error(&mut self, _symbol: Symbol, _skipped: u32) -> String {
+        unreachable!()
+    }
+}
+
+fn grammar() -> (Grammar, Symbol) {
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, x]);
+    external.set_start(start);
+    (external, x)
+}
+
+#[test]
+fn test_string_payload() {
+    let _ = env_logger::try_init();
+    let (external, x) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(x, "ab".to_string());
+    assert!(rec.end_earleme());
+    rec.scan(x, "cd".to_string());
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut evaluation = Evaluation::new(Concat, Ambiguity::Error);
+    let results = evaluation.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, Ok(vec!["abcd".to_string()]));
+}
+
+#[test]
+fn test_span_trees() {
+    let _ = env_logger::try_init();
+    let (external, x) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(x, 0..3usize);
+    assert!(rec.end_earleme());
+    rec.scan(x, 3..5);
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let trees: Vec<_> = rec.forest.traverse().trees(root).collect();
+    assert_eq!(
+        trees,
+        vec![Tree::Rule {
+            action: 0,
+            children: vec![
+                Tree::Leaf {
+                    symbol: x,
+                    value: 0..3
+                },
+                Tree::Leaf {
+                    symbol: x,
+                    value: 3..5
+                },
+            ],
+        }]
+    );
+}
+
+#[test]
+fn test_builder_payload() {
+    let _ = env_logger::try_init();
+    let (external, x) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(x, "abc");
+    assert!(rec.end_earleme());
+    rec.scan(x, "de");
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut builder = Builder::new();
+    builder
+        .terminal(x, |token: &str| token.len())
+        .rule(0, |args| args.iter().map(|&&len| len).sum());
+    let len = builder.build(&mut rec.forest.traverse(), root);
+    assert_eq!(len, Ok(5));
+}
+
+#[test]
+fn test_payload_rollback() {
+    let _ = env_logger::try_init();
+    let (external, x) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(x, "a".to_string());
+    assert!(rec.end_earleme());
+    let checkpoint = rec.checkpoint();
+    rec.scan(x, "b".to_string());
+    assert!(rec.end_earleme());
+    rec.rollback(checkpoint);
+    rec.scan(x, "c".to_string());
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut evaluation = Evaluation::new(Concat, Ambiguity::Error);
+    let results = evaluation.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, Ok(vec!["ac".to_string()]));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    text: &'static str,
+    line: u32,
+}
+
+impl LeafPayload for Token {}
+
+#[test]
+fn test_struct_payload() {
+    let _ = env_logger::try_init();
+    let (external, x) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(x, Token { text: "a", line: 1 });
+    assert!(rec.end_earleme());
+    rec.scan(x, Token { text: "b", line: 2 });
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut builder = Builder::new();
+    builder
+        .terminal(x, |token: Token| vec![(token.text, token.line)])
+        .rule(0, |args: &[&Vec<_>]| {
+            args.iter().flat_map(|arg| arg.iter().cloned()).collect()
+        });
+    let tokens = builder.build(&mut rec.forest.traverse(), root);
+    assert_eq!(tokens, Ok(vec![("a", 1), ("b", 2)]));
+}
+
+#[test]
+fn test_borrowed_payload() {
+    let _ = env_logger::try_init();
+    let (external, x) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let input = "ab cde".to_string();
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    for word in input.split(' ') {
+        rec.scan(x, word);
+        assert!(rec.end_earleme());
+    }
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut builder = Builder::new();
+    builder
+        .terminal(x, |token: &str| token.len())
+        .rule(0, |args| args.iter().map(|&&len| len).sum());
+    let len = builder.build(&mut rec.forest.traverse(), root);
+    assert_eq!(len, Ok(5));
+}
@@ -0,0 +1,212 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::compact_bocage::order::NullOrder as CompactNullOrder;
+use gearley::forest::{Bocage, CompactBocage, Forest, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::{Parse, SimpleCompactEvaluator, SimpleEvaluator};
+
+fn right_recursive_grammar() -> Grammar {
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    external
+}
+
+macro_rules! test_right_recursion {
+    ($Bocage:ident, $SimpleEvaluator:ident) => {
+        let _ = env_logger::try_init();
+        let external = right_recursive_grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let tokens = &[1; 100];
+        let mut evaluator = $SimpleEvaluator::new(
+            |_: Symbol| 1,
+            |_: u32, args: &[&u32]| args.iter().map(|&&arg| arg).sum(),
+            |_, _: &mut Vec<u32>| unreachable!(),
+        );
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        rec.set_leo(true);
+        assert!(rec.parse(tokens));
+        let mut traverse = rec.forest.traverse();
+        let results = evaluator.traverse(&mut traverse, rec.finished_node().unwrap());
+        assert_eq!(results, vec![100]);
+    };
+}
+
+#[test]
+fn test_right_recursion() {
+    test_right_recursion!(Bocage, SimpleEvaluator);
+}
+
+#[test]
+fn test_right_recursion_compact() {
+    test_right_recursion!(CompactBocage, SimpleCompactEvaluator);
+}
+
+macro_rules! test_linear_chart {
+    ($Bocage:ident, $without_leo:expr, $with_leo:expr) => {
+        let _ = env_logger::try_init();
+        let external = right_recursive_grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let x = Symbol::from(1u32);
+        let graph_len = |leo: bool| {
+            let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+            rec.set_leo(leo);
+            for i in 0..200 {
+                rec.scan(x, i);
+                assert!(rec.end_earleme());
+            }
+            assert!(rec.is_finished());
+            rec.forest.graph_len()
+        };
+        // Without Leo items, every earleme completes the whole chain.
+        assert!(graph_len(false) > $without_leo);
+        assert!(graph_len(true) < $with_leo);
+    };
+}
+
+#[test]
+fn test_linear_chart() {
+    test_linear_chart!(Bocage, 10_000, 2_000);
+}
+
+#[test]
+fn test_linear_chart_compact() {
+    test_linear_chart!(CompactBocage, 50_000, 5_000);
+}
+
+#[test]
+fn test_mark_compact_twice() {
+    let _ = env_logger::try_init();
+    let external = right_recursive_grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let x = Symbol::from(1u32);
+    let mut rec = Recognizer::new(&cfg, CompactBocage::new(&cfg));
+    rec.set_leo(true);
+    for i in 0..40 {
+        rec.scan(x, i);
+        assert!(rec.end_earleme());
+        if i == 19 {
+            let root = rec.finished_node().unwrap();
+            rec.forest.mark_alive(root, CompactNullOrder::new());
+        }
+    }
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, CompactNullOrder::new());
+    let mut evaluator = SimpleCompactEvaluator::new(
+        |_: Symbol| 1,
+        |_: u32, args: &[&u32]| args.iter().map(|&&arg| arg).sum(),
+        |_, _: &mut Vec<u32>| unreachable!(),
+    );
+    let results = evaluator.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, vec![40]);
+}
+
+#[test]
+fn test_ambiguous_right_recursion() {
+    let _ = env_logger::try_init();
+    let tokens = ambiguous_arith!('1' '2' '-' '3' '4' '5' '*' '6' '7' '+' '8' '9');
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let results = |leo: bool| {
+        let mut evaluator = SimpleEvaluator::new(
+            ambiguous_arith::leaf,
+            ambiguous_arith::rule,
+            |_, _: &mut Vec<i32>| unreachable!(),
+        );
+        let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+        rec.set_leo(leo);
+        assert!(rec.parse(tokens));
+        let mut traverse = rec.forest.traverse();
+        let mut results = evaluator.traverse(&mut traverse, rec.finished_node().unwrap());
+        results.sort();
+        results
+    };
+    assert_eq!(results(true), results(false));
+}
+
+#[test]
+fn test_checkpoint_with_leo() {
+    let _ = env_logger::try_init();
+    let external = right_recursive_grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let x = Symbol::from(1u32);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.set_leo(true);
+    for _ in 0..3 {
+        rec.scan(x, ());
+        assert!(rec.end_earleme());
+    }
+    let checkpoint = rec.checkpoint();
+    for _ in 0..3 {
+        rec.scan(x, ());
+        assert!(rec.end_earleme());
+    }
+    rec.rollback(checkpoint);
+    rec.scan(x, ());
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
+
+fn nullable_tail_grammar() -> Grammar {
+    let mut external = Grammar::new();
+    let (start, a, n, b, c) = external.sym();
+    external.rule(start).rhs([a, a, n]);
+    external.rule(a).rhs([n, c]).rhs([b, a]);
+    external.rule(n).rhs([]).rhs([b]);
+    external.set_start(start);
+    external
+}
+
+macro_rules! test_nullable_tail {
+    ($Bocage:ident, $SimpleEvaluator:ident) => {
+        let _ = env_logger::try_init();
+        let external = nullable_tail_grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let tokens = &[4, 3, 4];
+        let results = |leo: bool| {
+            let mut evaluator = $SimpleEvaluator::new(
+                |sym: Symbol| sym.usize().to_string(),
+                |action: u32, args: &[&String]| {
+                    let args: Vec<_> = args.iter().map(|arg| arg.as_str()).collect();
+                    format!("{}({})", action, args.join(" "))
+                },
+                |_, results: &mut Vec<String>| results.push("()".to_string()),
+            );
+            let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+            rec.set_leo(leo);
+            assert!(rec.parse(tokens));
+            let mut traverse = rec.forest.traverse();
+            let mut results = evaluator.traverse(&mut traverse, rec.finished_node().unwrap());
+            results.sort();
+            results
+        };
+        let without_leo = results(false);
+        assert_eq!(without_leo.len(), 2);
+        assert_eq!(results(true), without_leo);
+    };
+}
+
+#[test]
+fn test_nullable_tail() {
+    test_nullable_tail!(Bocage, SimpleEvaluator);
+}
+
+#[test]
+fn test_nullable_tail_compact() {
+    test_nullable_tail!(CompactBocage, SimpleCompactEvaluator);
+}
@@ -0,0 +1,52 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::driver::LongestMatch;
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::bocage::traverse::LeafHandle;
+use gearley::forest::{Bocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_longest_match() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (stmt, a, b, c) = external.sym();
+    external.rule(stmt).rhs([a]).rhs([a, b]).rhs([a, b, c]);
+    external.set_start(stmt);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut driver = LongestMatch::new(Recognizer::new(&cfg, Bocage::new(&cfg)));
+    let tokens = vec![(a, 0), (b, 1), (b, 2), (c, 3)];
+    let (location, node) = driver.parse(tokens).unwrap();
+    assert_eq!(location, 2);
+    let locations: Vec<_> = driver.finished().iter().map(|&(loc, _)| loc).collect();
+    assert_eq!(locations, vec![1, 2]);
+
+    let forest = &mut driver.recognizer.forest;
+    forest.mark_alive(node, NullOrder::new());
+    let mut traverse = forest.traverse();
+    let mut leaves = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let LeafHandle(value) = item.item {
+            leaves.push(value);
+        }
+    }
+    assert_eq!(leaves, vec![0, 1]);
+}
+
+#[test]
+fn test_no_match() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (stmt, a, b) = external.sym();
+    external.rule(stmt).rhs([a, a]);
+    external.set_start(stmt);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut driver = LongestMatch::new(Recognizer::new(&cfg, NullForest));
+    assert_eq!(driver.parse(vec![(a, ()), (b, ()), (a, ())]), None);
+    assert_eq!(driver.location(), 1);
+}
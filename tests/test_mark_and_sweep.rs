@@ -0,0 +1,210 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::eval::{Ambiguity, Evaluation, Evaluator};
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::{Bocage, Forest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::{Parse, SimpleEvaluator};
+
+#[test]
+fn test_sweep_ambiguous() {
+    let _ = env_logger::try_init();
+    let tokens = ambiguous_arith!('2' '-' '0' '*' '3' '+' '1');
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    assert!(rec.parse(tokens));
+    let root = rec.finished_node().unwrap();
+    let relocation = rec.forest.mark_and_sweep(root, NullOrder::new());
+    assert_eq!(relocation.len(), rec.forest.graph_len());
+    let root = relocation.get(root).unwrap();
+    let mut evaluator = SimpleEvaluator::new(
+        ambiguous_arith::leaf,
+        ambiguous_arith::rule,
+        |_, _: &mut Vec<i32>| unreachable!(),
+    );
+    let mut results = evaluator.traverse(&mut rec.forest.traverse(), root);
+    results.sort();
+    assert_eq!(results, vec![1, 2, 3, 7, 8]);
+}
+
+#[test]
+fn test_sweep_leo() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_leo(true);
+    assert!(rec.parse(&[1; 50]));
+    let root = rec.finished_node().unwrap();
+    let relocation = rec.forest.mark_and_sweep(root, NullOrder::new());
+    let root = relocation.get(root).unwrap();
+    let mut evaluator = SimpleEvaluator::new(
+        |_: Symbol| 1,
+        |_: u32, args: &[&u32]| args.iter().map(|&&arg| arg).sum(),
+        |_, _: &mut Vec<u32>| unreachable!(),
+    );
+    let results = evaluator.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, vec![50]);
+}
+
+struct Concat;
+
+impl Evaluator<&'static str> for Concat {
+    type Value = String;
+
+    fn leaf(&mut self, _symbol: Symbol, value: &'static str) -> String {
+        value.to_string()
+    }
+
+    fn rule(&mut self, _action: u32, args: &[&String]) -> String {
+        args.iter().map(|arg| &arg[..]).collect()
+    }
+
+    fn nulling(&mut self, _symbol: Symbol, _values: &mut Vec<String>) {
+        unreachable!()
+    }
+
+    fn error(&mut self, _symbol: Symbol, _skipped: u32) -> String {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_sweep_payloads() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x, y) = external.sym();
+    external.rule(start).rhs([x, x]).rhs([y, y]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(y, "dead");
+    rec.scan(x, "a");
+    assert!(rec.end_earleme());
+    rec.scan(x, "b");
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    let len = rec.forest.graph_len();
+    let relocation = rec.forest.mark_and_sweep(root, NullOrder::new());
+    assert!(rec.forest.graph_len() < len);
+    let root = relocation.get(root).unwrap();
+    let mut evaluation = Evaluation::new(Concat, Ambiguity::Error);
+    let results = evaluation.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, Ok(vec!["ab".to_string()]));
+}
+
+#[test]
+fn test_collect_between_earlemes() {
+    let _ = env_logger::try_init();
+    let tokens: &[u32] = ambiguous_arith!('2' '-' '0' '*' '3' '+' '1');
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (i, &token) in tokens[..4].iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    let len = rec.forest.graph_len();
+    let relocation = rec.collect_garbage();
+    assert_eq!(relocation.len(), rec.forest.graph_len());
+    assert!(rec.forest.graph_len() <= len);
+    for (i, &token) in tokens[4..].iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32 + 4);
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut evaluator = SimpleEvaluator::new(
+        ambiguous_arith::leaf,
+        ambiguous_arith::rule,
+        |_, _: &mut Vec<i32>| unreachable!(),
+    );
+    let mut results = evaluator.traverse(&mut rec.forest.traverse(), root);
+    results.sort();
+    assert_eq!(results, vec![1, 2, 3, 7, 8]);
+}
+
+#[test]
+fn test_collect_leo_chains() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_leo(true);
+    for i in 0..50 {
+        rec.scan(x, i);
+        if i % 10 == 5 {
+            // The completed token is still queued.
+            rec.collect_garbage();
+        }
+        assert!(rec.end_earleme());
+        if i % 10 == 0 {
+            rec.collect_garbage();
+        }
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut evaluator = SimpleEvaluator::new(
+        |_: Symbol| 1,
+        |_: u32, args: &[&u32]| args.iter().map(|&&arg| arg).sum(),
+        |_, _: &mut Vec<u32>| unreachable!(),
+    );
+    let results = evaluator.traverse(&mut rec.forest.traverse(), root);
+    assert_eq!(results, vec![50]);
+}
+
+#[test]
+fn test_collect_pending_tokens() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b, c) = external.sym();
+    external.rule(start).rhs([a, b]).rhs([c]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(c, "dead");
+    assert!(rec.end_earleme());
+    rec.reset();
+    rec.scan(a, "a");
+    rec.scan_with_length(c, "c", 2);
+    // Nodes of the first parse are removed.
+    let len = rec.forest.graph_len();
+    rec.collect_garbage();
+    assert!(rec.forest.graph_len() < len);
+    assert!(rec.end_earleme());
+    rec.collect_garbage();
+    rec.scan(b, "b");
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut evaluation = Evaluation::new(Concat, Ambiguity::All);
+    let mut results = evaluation
+        .traverse(&mut rec.forest.traverse(), root)
+        .unwrap();
+    results.sort();
+    assert_eq!(results, vec!["ab".to_string(), "c".to_string()]);
+}
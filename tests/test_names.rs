@@ -0,0 +1,132 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::eval::{BuildError, Builder};
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::{Bocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_names() {
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]);
+    external.set_start(start);
+    let mut cfg = InternalGrammar::from_grammar(&external);
+    cfg.set_symbol_name(a, "a");
+    cfg.set_rule_name(0, "pair");
+    assert_eq!(cfg.symbol_name(a), Some("a"));
+    assert_eq!(cfg.symbol_name(b), None);
+    assert_eq!(cfg.rule_name(0), Some("pair"));
+    assert_eq!(cfg.rule_name(1), None);
+    let copy = cfg.clone();
+    assert_eq!(copy.symbol_name(a), Some("a"));
+}
+
+#[test]
+fn test_named_parse_error() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]);
+    external.set_start(start);
+    let mut cfg = InternalGrammar::from_grammar(&external);
+    cfg.set_symbol_name(a, "open");
+    cfg.set_symbol_name(b, "close");
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    assert_eq!(rec.try_scan(a, ()), Ok(()));
+    assert_eq!(rec.try_end_earleme(), Ok(()));
+    let error = rec.try_scan(a, ()).unwrap_err();
+    assert_eq!(
+        error.display(&cfg).to_string(),
+        "parse error at earleme 1: unexpected token open; expected one of: close"
+    );
+    assert_eq!(
+        error.to_string(),
+        "parse error at earleme 1: unexpected token 1; expected one of: 2"
+    );
+}
+
+#[test]
+fn test_named_dot() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]).rhs([b, a]);
+    external.set_start(start);
+    let mut cfg = InternalGrammar::from_grammar(&external);
+    cfg.set_symbol_name(a, "a");
+    cfg.set_rule_name(0, "a then b");
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(a, 0);
+    assert!(rec.end_earleme());
+    rec.scan(b, 1);
+    assert!(rec.end_earleme());
+    let mut out = vec![];
+    rec.forest
+        .write_dot(rec.finished_node().unwrap(), &mut out)
+        .unwrap();
+    let dot = String::from_utf8(out).unwrap();
+    assert!(dot.contains("label=\"a then b\""));
+    assert!(dot.contains("label=\"a = 0\""));
+    assert!(dot.contains("label=\"2 = 1\""));
+}
+
+#[test]
+fn test_names_from_list() {
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar_with_names(&external, &["start", "a"]);
+    assert_eq!(cfg.symbol_name(start), Some("start"));
+    assert_eq!(cfg.symbol_name(a), Some("a"));
+    assert_eq!(cfg.symbol_name(b), None);
+}
+
+#[test]
+fn test_named_build_errors() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (expr, plus, num) = external.sym();
+    external.rule(expr).rhs([expr, plus, expr]).rhs([num]);
+    external.set_start(expr);
+    let cfg = InternalGrammar::from_grammar_with_names(&external, &["expr", "plus", "num"]);
+    let tokens = [(num, 1), (plus, 0), (num, 2), (plus, 0), (num, 3)];
+    let parse = |builder: &mut Builder<u32>, len: usize| {
+        let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+        for &(symbol, value) in &tokens[..len] {
+            rec.scan(symbol, value);
+            assert!(rec.end_earleme());
+        }
+        let root = rec.finished_node().unwrap();
+        rec.forest.mark_alive(root, NullOrder::new());
+        let mut traverse = rec.forest.traverse();
+        builder.build(&mut traverse, root)
+    };
+
+    let mut builder = Builder::new();
+    builder
+        .terminal(num, |value| value)
+        .rule(0, |args| args[0] + args[2])
+        .rule(1, |args| *args[0]);
+    let error = parse(&mut builder, 3).unwrap_err();
+    assert_eq!(error, BuildError::MissingTerminal(plus));
+    assert_eq!(
+        error.display(&cfg).to_string(),
+        "no constructor for terminal plus"
+    );
+    assert_eq!(error.to_string(), "no constructor for terminal 1");
+
+    builder.terminal(plus, |value| value);
+    assert_eq!(parse(&mut builder, 3), Ok(3));
+    let error = parse(&mut builder, 5).unwrap_err();
+    assert_eq!(
+        error.display(&cfg).to_string(),
+        "ambiguous node for symbol expr with 2 values"
+    );
+}
@@ -0,0 +1,89 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::Symbol;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::one_parse::{FirstSummand, SummandPolicy};
+use gearley::forest::{Bocage, Forest, OneParse};
+use gearley::grammar::InternalGrammar;
+use gearley::item::CompletedItem;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::SimpleEvaluator;
+
+fn evaluate<P>(tokens: &[u32], policy: P) -> (Vec<i32>, usize)
+where
+    P: for<'a> SummandPolicy<<Bocage<&'a InternalGrammar> as Forest>::NodeRef>,
+{
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let forest = OneParse::new(Bocage::new(&cfg), policy);
+    let mut rec = Recognizer::new(&cfg, forest);
+    for (i, &token) in tokens.iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    let root = rec.finished_node().unwrap();
+    let graph_len = rec.forest.graph_len();
+    let mut bocage = rec.forest.into_inner();
+    bocage.mark_alive(root, NullOrder::new());
+    let mut evaluator = SimpleEvaluator::new(
+        ambiguous_arith::leaf,
+        ambiguous_arith::rule,
+        |_, _: &mut Vec<i32>| unreachable!(),
+    );
+    let mut traverse = bocage.traverse();
+    (evaluator.traverse(&mut traverse, root), graph_len)
+}
+
+struct LastSummand;
+
+impl<N> SummandPolicy<N> for LastSummand {
+    fn prefer(&mut self, _chosen: &CompletedItem<N>, _candidate: &CompletedItem<N>) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test_one_parse() {
+    let _ = env_logger::try_init();
+    let tokens = ambiguous_arith!('2' '-' '0' '*' '3' '+' '1');
+    let (first, _) = evaluate(tokens, FirstSummand);
+    let (last, _) = evaluate(tokens, LastSummand);
+    assert_eq!(first.len(), 1);
+    assert_eq!(last.len(), 1);
+    // All parses are 2, 1, 3, 7 and 8.
+    assert!([2, 1, 3, 7, 8].contains(&first[0]));
+    assert!([2, 1, 3, 7, 8].contains(&last[0]));
+    assert!(first != last);
+}
+
+#[test]
+fn test_forest_size() {
+    let _ = env_logger::try_init();
+    let mut tokens = vec![];
+    for _ in 0..40 {
+        tokens.extend(ambiguous_arith!('1' '+'));
+    }
+    tokens.extend(ambiguous_arith!('1'));
+    let (results, graph_len) = evaluate(&tokens, FirstSummand);
+    assert_eq!(results, vec![41]);
+
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (i, &token) in tokens.iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    assert!(graph_len * 4 < rec.forest.graph_len());
+}
@@ -0,0 +1,55 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::error::ParseError;
+use gearley::forest::NullForest;
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_rejected_token() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    assert_eq!(rec.try_scan(a, ()), Ok(()));
+    assert_eq!(rec.try_end_earleme(), Ok(()));
+    let error = rec.try_scan(a, ()).unwrap_err();
+    assert_eq!(
+        error,
+        ParseError {
+            earleme: 1,
+            token: Some(a),
+            expected: vec![b],
+            partial_rules: vec![((0, 1), 0)],
+        }
+    );
+    // The recognizer is left intact.
+    assert_eq!(rec.try_scan(b, ()), Ok(()));
+    assert_eq!(rec.try_end_earleme(), Ok(()));
+    assert!(rec.is_finished());
+}
+
+#[test]
+fn test_exhausted() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a) = external.sym();
+    external.rule(start).rhs([a]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    assert_eq!(rec.parse_error(None).expected, vec![a]);
+    rec.scan(a, ());
+    assert_eq!(rec.try_end_earleme(), Ok(()));
+    let error = rec.try_end_earleme().unwrap_err();
+    assert_eq!(error.token, None);
+    assert!(error.expected.is_empty());
+    assert_eq!(error.to_string(), "parse error at earleme 1: exhausted");
+}
@@ -0,0 +1,185 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::bocage::order::{Order, RankOrder, RankReorder};
+use gearley::forest::compact_bocage::order::Order as CompactOrder;
+use gearley::forest::{Bocage, CompactBocage};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use helpers::{SimpleCompactEvaluator, SimpleEvaluator};
+
+fn grammar() -> (InternalGrammar, Symbol) {
+    let mut external = Grammar::new();
+    let (start, a, b, x) = external.sym();
+    // Rules 0 to 3.
+    external.rule(start).rhs([a]).rhs([b]);
+    external.rule(a).rhs([x]);
+    external.rule(b).rhs([x]);
+    external.set_start(start);
+    let mut cfg = InternalGrammar::from_grammar(&external);
+    cfg.set_rule_rank(1, 1);
+    (cfg, x)
+}
+
+// Removes the alternatives that have the symbol `b` as a factor.
+struct AvoidB<'g> {
+    grammar: &'g InternalGrammar,
+}
+
+impl<'g> AvoidB<'g> {
+    fn new(grammar: &'g InternalGrammar) -> Self {
+        AvoidB { grammar }
+    }
+
+    fn position(&self, factors: &[(Symbol, u32)]) -> Option<usize> {
+        let b = Symbol::from(2usize);
+        factors
+            .iter()
+            .position(|&(sym, _)| self.grammar.to_external(sym) == b)
+    }
+}
+
+impl<'g> Order for AvoidB<'g> {
+    fn product(&mut self, factors: &[(Symbol, u32)]) -> Option<usize> {
+        self.position(factors)
+    }
+}
+
+impl<'g> CompactOrder for AvoidB<'g> {
+    fn product(&mut self, factors: &[(Symbol, u32)]) -> Option<usize> {
+        self.position(factors)
+    }
+}
+
+macro_rules! test_rank_order {
+    ($Bocage:ident, $SimpleEvaluator:ident, $order:expr, $expected:expr) => {
+        let _ = env_logger::try_init();
+        let (cfg, x) = grammar();
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        rec.scan(x, 0);
+        assert!(rec.end_earleme());
+        let root = rec.finished_node().unwrap();
+        rec.forest.mark_alive(root, $order(&cfg));
+        let mut evaluator = $SimpleEvaluator::new(
+            |_: Symbol| 0,
+            |rule: u32, args: &[&u32]| rule * 10 + *args[0],
+            |_, _: &mut Vec<u32>| unreachable!(),
+        );
+        let mut traverse = rec.forest.traverse();
+        let results = evaluator.traverse(&mut traverse, root);
+        assert_eq!(results, $expected);
+    };
+}
+
+#[test]
+fn test_filter() {
+    test_rank_order!(Bocage, SimpleEvaluator, RankOrder::new, vec![40]);
+}
+
+#[test]
+fn test_filter_compact() {
+    test_rank_order!(
+        CompactBocage,
+        SimpleCompactEvaluator,
+        RankOrder::new,
+        vec![40]
+    );
+}
+
+#[test]
+fn test_reorder() {
+    test_rank_order!(Bocage, SimpleEvaluator, RankReorder::new, vec![40, 20]);
+}
+
+#[test]
+fn test_product_order() {
+    test_rank_order!(Bocage, SimpleEvaluator, AvoidB::new, vec![20]);
+}
+
+#[test]
+fn test_product_order_compact() {
+    test_rank_order!(CompactBocage, SimpleCompactEvaluator, AvoidB::new, vec![20]);
+}
+
+// Removes the alternatives that have any of the given external symbols as a factor.
+struct AvoidSymbols<'g> {
+    grammar: &'g InternalGrammar,
+    symbols: Vec<Symbol>,
+}
+
+impl<'g> AvoidSymbols<'g> {
+    fn position(&self, factors: &[(Symbol, u32)]) -> Option<usize> {
+        factors
+            .iter()
+            .position(|&(sym, _)| self.symbols.contains(&self.grammar.to_external(sym)))
+    }
+}
+
+impl<'g> Order for AvoidSymbols<'g> {
+    fn product(&mut self, factors: &[(Symbol, u32)]) -> Option<usize> {
+        self.position(factors)
+    }
+}
+
+impl<'g> CompactOrder for AvoidSymbols<'g> {
+    fn product(&mut self, factors: &[(Symbol, u32)]) -> Option<usize> {
+        self.position(factors)
+    }
+}
+
+macro_rules! test_remove_sum {
+    ($Bocage:ident, $avoid:expr, $expected:expr) => {
+        let _ = env_logger::try_init();
+        let mut external = Grammar::new();
+        let (start, p, q, a, b, x) = external.sym();
+        external.rule(start).rhs([p]).rhs([q]);
+        external.rule(p).rhs([a]).rhs([b]);
+        external.rule(q).rhs([x]);
+        external.rule(a).rhs([x]);
+        external.rule(b).rhs([x]);
+        external.set_start(start);
+        let cfg = InternalGrammar::from_grammar(&external);
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        rec.scan(x, 0);
+        assert!(rec.end_earleme());
+        let root = rec.finished_node().unwrap();
+        let symbols = $avoid(a, b, q);
+        rec.forest.mark_alive(
+            root,
+            AvoidSymbols {
+                grammar: &cfg,
+                symbols,
+            },
+        );
+        assert_eq!(rec.forest.traverse().trees(root).count(), $expected);
+    };
+}
+
+#[test]
+fn test_remove_sum() {
+    test_remove_sum!(Bocage, |a, b, _q| vec![a, b], 1);
+}
+
+#[test]
+fn test_remove_sum_compact() {
+    test_remove_sum!(CompactBocage, |a, b, _q| vec![a, b], 1);
+}
+
+#[test]
+fn test_remove_all() {
+    test_remove_sum!(Bocage, |a, b, q| vec![a, b, q], 0);
+}
+
+#[test]
+fn test_remove_all_compact() {
+    test_remove_sum!(CompactBocage, |a, b, q| vec![a, b, q], 0);
+}
@@ -0,0 +1,160 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::bocage::traverse::ErrorHandle;
+use gearley::forest::compact_bocage::order::NullOrder as CompactNullOrder;
+use gearley::forest::compact_bocage::traverse::ErrorHandle as CompactErrorHandle;
+use gearley::forest::{Bocage, CompactBocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+use gearley::recovery::{ErrorRecovery, Repair};
+
+fn grammar() -> (Grammar, [Symbol; 4]) {
+    let mut external = Grammar::new();
+    let (program, stmts, stmt) = external.sym();
+    let (a, semi, error, b) = external.sym();
+    external.rule(program).rhs([stmts]);
+    external.rule(stmts).rhs([stmt]).rhs([stmts, stmt]);
+    external.rule(stmt).rhs([a, semi]).rhs([error, semi]);
+    external.set_start(program);
+    (external, [a, semi, error, b])
+}
+
+#[test]
+fn test_skip_tokens() {
+    let _ = env_logger::try_init();
+    let (external, [a, semi, error, b]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_error_recovery(Some(ErrorRecovery {
+        error_symbol: error,
+        token_matches: 2,
+    }));
+    let tokens = &[a, semi, b, b, semi, a, semi];
+    let mut i = 0;
+    while i < tokens.len() {
+        if rec.try_scan(tokens[i], i as u32).is_ok() {
+            assert!(rec.end_earleme());
+            i += 1;
+        } else {
+            let repair = rec.recover(&tokens[i..]);
+            assert_eq!(repair, Some(Repair { skipped: 2 }));
+            i += 2;
+        }
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut errors = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let ErrorHandle(skipped) = item.item {
+            errors.push((item.symbol, skipped));
+        }
+    }
+    assert_eq!(errors, vec![(error, 2)]);
+}
+
+#[test]
+fn test_skip_tokens_compact() {
+    let _ = env_logger::try_init();
+    let (external, [a, semi, error, b]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, CompactBocage::new(&cfg));
+    rec.set_error_recovery(Some(ErrorRecovery {
+        error_symbol: error,
+        token_matches: 2,
+    }));
+    let tokens = &[a, semi, b, b, semi, a, semi];
+    let mut i = 0;
+    while i < tokens.len() {
+        if rec.try_scan(tokens[i], i as u32).is_ok() {
+            assert!(rec.end_earleme());
+            i += 1;
+        } else {
+            let repair = rec.recover(&tokens[i..]);
+            assert_eq!(repair, Some(Repair { skipped: 2 }));
+            i += 2;
+        }
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, CompactNullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut errors = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let CompactErrorHandle(skipped) = item.item {
+            errors.push((item.symbol, skipped));
+        }
+        item.end_evaluation();
+    }
+    assert_eq!(errors, vec![(error, 2)]);
+}
+
+#[test]
+fn test_repair_from_earlier_set() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (program, stmts, stmt) = external.sym();
+    let (a, semi, error, b) = external.sym();
+    external.rule(program).rhs([stmts]);
+    external.rule(stmts).rhs([stmt]).rhs([stmts, stmt]);
+    external.rule(stmt).rhs([a, a, semi]).rhs([error, semi]);
+    external.set_start(program);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_error_recovery(Some(ErrorRecovery {
+        error_symbol: error,
+        token_matches: 2,
+    }));
+    let tokens = &[a, semi, b, semi, a, a, semi];
+    rec.scan(a, 0);
+    assert!(rec.end_earleme());
+    // The error token starts before `a`, where it is expected.
+    assert!(!rec.accepts(error));
+    let repair = rec.recover(&tokens[1..]);
+    assert_eq!(repair, Some(Repair { skipped: 2 }));
+    for (i, &token) in tokens.iter().enumerate().skip(3) {
+        rec.scan(token, i as u32);
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut errors = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let ErrorHandle(skipped) = item.item {
+            errors.push((item.symbol, skipped));
+        }
+    }
+    assert_eq!(errors, vec![(error, 2)]);
+}
+
+#[test]
+fn test_no_repair() {
+    let _ = env_logger::try_init();
+    let (external, [a, semi, error, b]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    let tokens = &[a, semi, b];
+    for &token in &tokens[..2] {
+        rec.scan(token, ());
+        assert!(rec.end_earleme());
+    }
+    // Recovery is disabled.
+    assert_eq!(rec.recover(&tokens[2..]), None);
+    rec.set_error_recovery(Some(ErrorRecovery {
+        error_symbol: error,
+        token_matches: 1,
+    }));
+    // The input ends before the error token can be followed by `semi`.
+    assert_eq!(rec.recover(&tokens[2..]), None);
+    // The recognizer is left intact.
+    assert_eq!(rec.try_scan(a, ()), Ok(()));
+}
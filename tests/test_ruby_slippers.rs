@@ -0,0 +1,120 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::bocage::traverse::{LeafHandle, VirtualHandle};
+use gearley::forest::compact_bocage::order::NullOrder as CompactNullOrder;
+use gearley::forest::compact_bocage::traverse::{
+    LeafHandle as CompactLeafHandle, VirtualHandle as CompactVirtualHandle,
+};
+use gearley::forest::{Bocage, CompactBocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+fn grammar() -> (Grammar, [Symbol; 2]) {
+    let mut external = Grammar::new();
+    let (stmts, stmt, a, semi) = external.sym();
+    external.rule(stmts).rhs([stmt]).rhs([stmts, stmt]);
+    external.rule(stmt).rhs([a, semi]);
+    external.set_start(stmts);
+    (external, [a, semi])
+}
+
+#[test]
+fn test_inject_semicolon() {
+    let _ = env_logger::try_init();
+    let (external, [a, semi]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    let tokens = &[a, a, semi];
+    for (i, &token) in tokens.iter().enumerate() {
+        if !rec.accepts(token) {
+            assert_eq!(rec.virtual_candidates(token), vec![semi]);
+            assert!(rec.scan_virtual(semi, 100, token));
+        }
+        rec.scan(token, i as u32);
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut leaves = vec![];
+    while let Some(item) = traverse.next_node() {
+        match item.item {
+            LeafHandle(value) => leaves.push((item.symbol, value, false)),
+            VirtualHandle(value) => leaves.push((item.symbol, value, true)),
+            _ => {}
+        }
+    }
+    leaves.sort();
+    assert_eq!(
+        leaves,
+        vec![
+            (a, 0, false),
+            (a, 1, false),
+            (semi, 2, false),
+            (semi, 100, true)
+        ]
+    );
+}
+
+#[test]
+fn test_inject_semicolon_compact() {
+    let _ = env_logger::try_init();
+    let (external, [a, semi]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, CompactBocage::new(&cfg));
+    let tokens = &[a, a, semi];
+    for (i, &token) in tokens.iter().enumerate() {
+        if !rec.accepts(token) {
+            assert!(rec.scan_virtual(semi, 100, token));
+        }
+        rec.scan(token, i as u32);
+        assert!(rec.end_earleme());
+    }
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, CompactNullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut leaves = vec![];
+    while let Some(item) = traverse.next_node() {
+        match item.item {
+            CompactLeafHandle(value) => leaves.push((item.symbol, value, false)),
+            CompactVirtualHandle(value) => leaves.push((item.symbol, value, true)),
+            _ => {}
+        }
+        item.end_evaluation();
+    }
+    leaves.sort();
+    assert_eq!(
+        leaves,
+        vec![
+            (a, 0, false),
+            (a, 1, false),
+            (semi, 2, false),
+            (semi, 100, true)
+        ]
+    );
+}
+
+#[test]
+fn test_injection_undone() {
+    let _ = env_logger::try_init();
+    let (external, [a, semi]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    // `semi semi` is rejected, so nothing is injected.
+    assert!(rec.virtual_candidates(semi).is_empty());
+    assert!(!rec.scan_virtual(semi, (), semi));
+    assert_eq!(rec.earleme(), 1);
+    rec.scan(semi, ());
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
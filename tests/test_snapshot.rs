@@ -0,0 +1,128 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::error::SnapshotError;
+use gearley::forest::{Bocage, CompactBocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::{Parse, SimpleEvaluator};
+
+#[test]
+fn test_suspend_and_resume() {
+    let _ = env_logger::try_init();
+    let tokens = ambiguous_arith!('2' '-' '0' '*' '3' '+' '1');
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (i, &token) in tokens[..4].iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    let snapshot = rec.snapshot().unwrap();
+    let mut rec = Recognizer::restore(&cfg, Bocage::new(&cfg), &snapshot[..]).unwrap();
+    assert_eq!(rec.earleme(), 4);
+    assert!(rec.parse(&tokens[4..]));
+    let mut evaluator = SimpleEvaluator::new(
+        ambiguous_arith::leaf,
+        ambiguous_arith::rule,
+        |_, _: &mut Vec<i32>| unreachable!(),
+    );
+    let mut traverse = rec.forest.traverse();
+    let results = evaluator.traverse(&mut traverse, rec.finished_node().unwrap());
+    assert_eq!(results, vec![2, 1, 3, 7, 8]);
+}
+
+#[test]
+fn test_grammar_mismatch() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.scan(a, ());
+    assert!(rec.end_earleme());
+    let snapshot = rec.snapshot().unwrap();
+
+    external.rule(start).rhs([b, a]);
+    let other_cfg = InternalGrammar::from_grammar(&external);
+    let result = Recognizer::restore(&other_cfg, NullForest, &snapshot[..]);
+    assert_eq!(result.err(), Some(SnapshotError::GrammarMismatch));
+
+    let truncated = &snapshot[..snapshot.len() - 1];
+    let result = Recognizer::restore(&cfg, NullForest, truncated);
+    assert_eq!(result.err(), Some(SnapshotError::Malformed));
+
+    let mut rec = Recognizer::restore(&cfg, NullForest, &snapshot[..]).unwrap();
+    rec.scan(b, ());
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
+
+#[test]
+fn test_corrupt_graph() {
+    let _ = env_logger::try_init();
+    let tokens: &[u32] = ambiguous_arith!('2' '-' '0' '*' '3');
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    for (i, &token) in tokens.iter().enumerate() {
+        rec.scan(Symbol::from(token), i as u32);
+        assert!(rec.end_earleme());
+    }
+    let snapshot = rec.snapshot().unwrap();
+    // The graph's length follows the magic number, the version and the fingerprint.
+    let mut len = [0; 8];
+    len.copy_from_slice(&snapshot[16..24]);
+    let graph = 24..24 + 12 * u64::from_le_bytes(len) as usize;
+    let mut malformed = 0;
+    for pos in graph {
+        let mut corrupt = snapshot.clone();
+        corrupt[pos] ^= 0xFF;
+        if let Err(error) = Recognizer::restore(&cfg, Bocage::new(&cfg), &corrupt[..]) {
+            assert_eq!(error, SnapshotError::Malformed);
+            malformed += 1;
+        }
+    }
+    assert!(malformed > 0);
+}
+
+#[test]
+fn test_unsupported_forest() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a) = external.sym();
+    external.rule(start).rhs([a, a]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+
+    let mut rec = Recognizer::new(&cfg, Bocage::with_payload(&cfg));
+    rec.scan(a, "a".to_string());
+    assert!(rec.end_earleme());
+    assert_eq!(rec.snapshot().err(), Some(SnapshotError::UnsupportedForest));
+
+    let mut rec = Recognizer::new(&cfg, CompactBocage::new(&cfg));
+    rec.scan(a, 0);
+    assert!(rec.end_earleme());
+    assert_eq!(rec.snapshot().err(), Some(SnapshotError::UnsupportedForest));
+
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(a, 0);
+    assert!(rec.end_earleme());
+    let snapshot = rec.snapshot().unwrap();
+    let result = Recognizer::restore(&cfg, CompactBocage::new(&cfg), &snapshot[..]);
+    assert_eq!(result.err(), Some(SnapshotError::UnsupportedForest));
+}
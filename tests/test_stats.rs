@@ -0,0 +1,108 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::forest::{Bocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+use gearley::recovery::ErrorRecovery;
+
+use grammars::*;
+use helpers::Parse;
+
+#[test]
+fn test_stats_disabled() {
+    let _ = env_logger::try_init();
+    let external = precedenced_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    assert!(rec.parse(precedenced_arith!('1' '+' '2')));
+    assert_eq!(rec.stats(), None);
+}
+
+#[test]
+fn test_stats_arith() {
+    let _ = env_logger::try_init();
+    let external = precedenced_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_stats(true);
+    assert!(rec.parse(precedenced_arith!('1' '+' '(' '2' '*' '3' ')')));
+    let stats = rec.stats().unwrap();
+    assert_eq!(stats.items_per_set, vec![6, 1, 1, 5, 1, 5, 2]);
+    assert_eq!(stats.items(), 21);
+    assert_eq!(stats.unary_items_removed, 11);
+    assert_eq!(stats.sets_dropped, 5);
+    assert_eq!(stats.heap_pushes, 17);
+    assert_eq!(stats.heap_pops, 17);
+    assert_eq!(stats.lookahead_rejections, 14);
+    assert_eq!(stats.forest_nodes, 24);
+}
+
+fn right_recursion_heap_pushes(leo: bool) -> usize {
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, start]).rhs([x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.set_leo(leo);
+    rec.set_stats(true);
+    assert!(rec.parse(&[1; 20]));
+    let stats = rec.stats().unwrap();
+    assert_eq!(stats.items_per_set.len(), 20);
+    assert_eq!(stats.heap_pops, stats.heap_pushes);
+    assert_eq!(stats.forest_nodes, 0);
+    stats.heap_pushes
+}
+
+#[test]
+fn test_stats_leo() {
+    let _ = env_logger::try_init();
+    assert!(right_recursion_heap_pushes(true) < right_recursion_heap_pushes(false));
+}
+
+#[test]
+fn test_stats_rollback() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, stmt) = external.sym();
+    let (a, semi, error, b): (Symbol, Symbol, Symbol, Symbol) = external.sym();
+    external.rule(start).rhs([stmt]).rhs([start, stmt]);
+    external.rule(stmt).rhs([a, semi]).rhs([error, semi]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.set_stats(true);
+    for (i, &token) in [a, semi].iter().enumerate() {
+        rec.scan(token, i as u32);
+        assert!(rec.end_earleme());
+    }
+    let saved = rec.stats().cloned();
+
+    let checkpoint = rec.checkpoint();
+    for (i, &token) in [a, semi].iter().enumerate() {
+        rec.scan(token, i as u32);
+        assert!(rec.end_earleme());
+    }
+    assert_ne!(rec.stats().cloned(), saved);
+    rec.rollback(checkpoint);
+    assert_eq!(rec.stats().cloned(), saved);
+
+    rec.set_error_recovery(Some(ErrorRecovery {
+        error_symbol: error,
+        token_matches: 1,
+    }));
+    // Recovery tries to repair the input, but fails.
+    assert_eq!(rec.recover(&[b]), None);
+    assert_eq!(rec.stats().cloned(), saved);
+}
@@ -0,0 +1,71 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+use cfg::Symbol;
+
+use gearley::driver::Substring;
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::bocage::traverse::LeafHandle;
+use gearley::forest::{Bocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+fn grammar() -> (Grammar, [Symbol; 3]) {
+    let mut external = Grammar::new();
+    let (pattern, tail, a, b, c) = external.sym();
+    external.rule(pattern).rhs([a, tail]);
+    external.rule(tail).rhs([b]).rhs([tail, b]);
+    external.set_start(pattern);
+    (external, [a, b, c])
+}
+
+#[test]
+fn test_find_all() {
+    let _ = env_logger::try_init();
+    let (external, [a, b, c]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut driver = Substring::new(Recognizer::new(&cfg, Bocage::new(&cfg)));
+    let tokens = [c, a, b, b, c, a, b];
+    let matches = driver
+        .find_all(tokens.iter().enumerate().map(|(i, &sym)| (sym, i as u32)))
+        .to_vec();
+    let spans: Vec<_> = matches.iter().map(|m| (m.start, m.end)).collect();
+    assert_eq!(spans, vec![(1, 3), (1, 4), (5, 7)]);
+
+    let forest = &mut driver.recognizer.forest;
+    forest.mark_alive(matches[1].node, NullOrder::new());
+    let mut traverse = forest.traverse();
+    let mut leaves = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let LeafHandle(value) = item.item {
+            leaves.push(value);
+        }
+    }
+    leaves.sort();
+    assert_eq!(leaves, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_locations_after_removed_sets() {
+    let _ = env_logger::try_init();
+    let (external, [a, b, c]) = grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut driver = Substring::new(Recognizer::new(&cfg, NullForest));
+    let mut tokens = vec![a, b];
+    tokens.extend(vec![c; 10]);
+    tokens.push(a);
+    tokens.extend(vec![b; 10]);
+    let spans: Vec<_> = driver
+        .find_all(tokens.into_iter().map(|sym| (sym, ())))
+        .iter()
+        .map(|m| (m.start, m.end))
+        .collect();
+    let mut expected = vec![(0, 2)];
+    expected.extend((14..=23).map(|end| (12, end)));
+    assert_eq!(spans, expected);
+    assert_eq!(driver.location(), 23);
+    // Earlier sets were removed.
+    assert!(driver.recognizer.earleme() < 23);
+}
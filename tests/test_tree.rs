@@ -0,0 +1,121 @@
+#[macro_use]
+extern crate log;
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+#[macro_use]
+mod grammars;
+mod helpers;
+
+use cfg::earley::Grammar;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::{Bocage, CompactBocage, Tree};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+use grammars::*;
+use helpers::{Parse, SimpleCompactEvaluator, SimpleEvaluator};
+
+fn evaluate(tree: &Tree) -> i32 {
+    match *tree {
+        Tree::Rule {
+            action,
+            ref children,
+        } => {
+            let values: Vec<_> = children.iter().map(evaluate).collect();
+            let args: Vec<_> = values.iter().collect();
+            ambiguous_arith::rule(action, &args[..])
+        }
+        Tree::Leaf { symbol, .. } => ambiguous_arith::leaf(symbol),
+        _ => unreachable!(),
+    }
+}
+
+macro_rules! test_trees {
+    ($Bocage:ident, $SimpleEvaluator:ident) => {
+        let _ = env_logger::try_init();
+        let tokens = ambiguous_arith!('2' '-' '0' '*' '3' '+' '1');
+        let external = ambiguous_arith::grammar();
+        let cfg = InternalGrammar::from_grammar(&external);
+        let expected = {
+            let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+            assert!(rec.parse(tokens));
+            let mut evaluator = $SimpleEvaluator::new(
+                ambiguous_arith::leaf,
+                ambiguous_arith::rule,
+                |_, _: &mut Vec<i32>| unreachable!(),
+            );
+            let mut traverse = rec.forest.traverse();
+            let mut results = evaluator.traverse(&mut traverse, rec.finished_node().unwrap());
+            results.sort();
+            results
+        };
+        let mut rec = Recognizer::new(&cfg, $Bocage::new(&cfg));
+        assert!(rec.parse(tokens));
+        let mut traverse = rec.forest.traverse();
+        let trees = traverse.trees(rec.finished_node().unwrap());
+        let mut results: Vec<_> = trees.map(|tree| evaluate(&tree)).collect();
+        results.sort();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results, expected);
+    };
+}
+
+#[test]
+fn test_trees() {
+    test_trees!(Bocage, SimpleEvaluator);
+}
+
+#[test]
+fn test_trees_compact() {
+    test_trees!(CompactBocage, SimpleCompactEvaluator);
+}
+
+#[test]
+fn test_distinct_trees_with_limit() {
+    let _ = env_logger::try_init();
+    let tokens = ambiguous_arith!('1' '+' '2' '+' '3' '+' '4' '+' '5' '+' '6' '+' '7');
+    let external = ambiguous_arith::grammar();
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    assert!(rec.parse(tokens));
+    let mut traverse = rec.forest.traverse();
+    let trees: Vec<_> = traverse
+        .trees(rec.finished_node().unwrap())
+        .with_limit(100)
+        .collect();
+    // There are 132 ways to bracket seven numbers.
+    assert_eq!(trees.len(), 100);
+    for (i, tree) in trees.iter().enumerate() {
+        assert!(!trees[..i].contains(tree));
+        assert_eq!(evaluate(tree), 28);
+    }
+}
+
+#[test]
+fn test_leaf_values() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, x) = external.sym();
+    external.rule(start).rhs([x, x]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(x, 10);
+    assert!(rec.end_earleme());
+    rec.scan(x, 20);
+    assert!(rec.end_earleme());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let trees: Vec<_> = rec.forest.traverse().trees(root).collect();
+    let leaf = |value| Tree::Leaf { symbol: x, value };
+    assert_eq!(
+        trees,
+        vec![Tree::Rule {
+            action: 0,
+            children: vec![leaf(10), leaf(20)],
+        }]
+    );
+}
@@ -0,0 +1,80 @@
+extern crate cfg;
+extern crate env_logger;
+extern crate gearley;
+
+use cfg::earley::Grammar;
+
+use gearley::forest::bocage::order::NullOrder;
+use gearley::forest::bocage::traverse::LeafHandle;
+use gearley::forest::{Bocage, NullForest};
+use gearley::grammar::InternalGrammar;
+use gearley::recognizer::Recognizer;
+
+#[test]
+fn test_character_stream() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (stmt, kw_if, space, ident) = external.sym();
+    external.rule(stmt).rhs([kw_if, space, ident]);
+    external.set_start(stmt);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    // `if x`
+    rec.scan_with_length(kw_if, (), 2);
+    assert!(rec.end_earleme());
+    // Nothing ends at `f`.
+    assert!(!rec.is_exhausted());
+    assert!(rec.end_earleme());
+    rec.scan(space, ());
+    assert!(rec.end_earleme());
+    rec.scan_with_length(ident, (), 1);
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+}
+
+#[test]
+fn test_overlapping_tokens() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b, c) = external.sym();
+    external.rule(start).rhs([a, b]).rhs([c]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, Bocage::new(&cfg));
+    rec.scan(a, 0);
+    rec.scan_with_length(c, 1, 2);
+    assert!(rec.end_earleme());
+    rec.scan(b, 2);
+    assert!(rec.end_earleme());
+    assert!(rec.is_finished());
+    let root = rec.finished_node().unwrap();
+    rec.forest.mark_alive(root, NullOrder::new());
+    let mut traverse = rec.forest.traverse();
+    let mut leaves = vec![];
+    while let Some(item) = traverse.next_node() {
+        if let LeafHandle(value) = item.item {
+            leaves.push((value, item.symbol));
+        }
+    }
+    leaves.sort();
+    assert_eq!(leaves, vec![(0, a), (1, c), (2, b)]);
+}
+
+#[test]
+fn test_no_completion_without_end() {
+    let _ = env_logger::try_init();
+    let mut external = Grammar::new();
+    let (start, a, b) = external.sym();
+    external.rule(start).rhs([a, b]);
+    external.set_start(start);
+    let cfg = InternalGrammar::from_grammar(&external);
+    let mut rec = Recognizer::new(&cfg, NullForest);
+    rec.scan_with_length(a, (), 3);
+    assert!(rec.end_earleme());
+    assert!(!rec.accepts(b));
+    assert!(rec.end_earleme());
+    assert!(!rec.accepts(b));
+    assert!(rec.end_earleme());
+    assert!(rec.accepts(b));
+    assert!(!rec.end_earleme());
+}